@@ -1,31 +1,66 @@
 use std::{u8, fs};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::convert::From;
 use std::str::FromStr;
 use std::io::Read;
 use std::process::exit;
 use std::collections::btree_set::Iter;
 use std::slice::Iter as VecIter;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, BTreeMap};
 use std::cmp::{PartialOrd, Ordering};
+use std::time::Duration;
 
 use colored::Colorize;
 use toml::{Parser, Value};
 
 use static_analysis::manifest::Permission;
+use results::{ReportFormat, Lang, Selector};
 
-use {Error, Result, Criticity, print_error, print_warning, file_exists};
+use {Error, Result, Criticity, Confidence, print_error, print_warning, file_exists};
 
 const MAX_THREADS: i64 = u8::MAX as i64;
 
-#[derive(Debug)]
+/// Name of the file with the analysis options of a project.
+pub const PROJECT_FILE: &'static str = "super.toml";
+
+/// Options of the `super.toml` file of a project.
+const PROJECT_OPTIONS: [&'static str; 37] = ["threads", "single_thread", "min_confidence", "format",
+                                             "output", "output_dir", "output_json_gz",
+                                             "output_txt", "output_by_file", "baseline",
+                                             "post_hook", "lang",
+                                             "rule_overrides",
+                                             "known_good", "file_timeout", "mmap_threshold",
+                                             "max_findings", "select", "cache",
+                                             "strict_rules",
+                                             "absolute_paths",
+                                             "require_files", "follow_symlinks", "include_smali",
+                                             "include_generated", "redact_secrets",
+                                             "normalize_newlines", "manifest_analysis",
+                                             "certificate_analysis", "code_analysis", "rules",
+                                             "extensions", "skip_packages",
+                                             "enable_categories", "disable_categories",
+                                             "include_tags", "exclude_tags"];
+
+/// Options that can be set in the `super.toml` file of the analyzed application. Since the
+/// application is not trusted, its file cannot run commands, choose where files are written or
+/// read, or hide findings.
+const APP_ROOT_OPTIONS: [&'static str; 15] = ["threads", "single_thread", "format", "lang",
+                                              "mmap_threshold", "cache", "strict_rules",
+                                              "absolute_paths", "require_files", "include_smali",
+                                              "include_generated", "redact_secrets",
+                                              "normalize_newlines", "enable_categories",
+                                              "include_tags"];
+
+#[derive(Debug, Clone)]
 pub struct Config {
     app_id: String,
     verbose: bool,
     quiet: bool,
+    debug: bool,
     force: bool,
     bench: bool,
     threads: u8,
+    single_thread: bool,
     downloads_folder: String,
     dist_folder: String,
     results_folder: String,
@@ -37,6 +72,48 @@ pub struct Config {
     unknown_permission: (Criticity, String),
     permissions: BTreeSet<PermissionConfig>,
     loaded_files: Vec<String>,
+    min_confidence: Confidence,
+    output_json_gz: Option<String>,
+    output_txt: Option<String>,
+    output_by_file: Option<String>,
+    post_hook: Option<Vec<String>>,
+    report_formats: Vec<ReportFormat>,
+    output: Option<String>,
+    output_dir: Option<String>,
+    lang: Lang,
+    enabled_categories: Vec<String>,
+    disabled_categories: Vec<String>,
+    included_tags: Vec<String>,
+    excluded_tags: Vec<String>,
+    cache_folder: String,
+    cache: bool,
+    file_timeout: Option<Duration>,
+    mmap_threshold: Option<u64>,
+    rules_files: Vec<String>,
+    source_folder: Option<String>,
+    rule_overrides: Option<String>,
+    known_good: Option<String>,
+    extensions: Vec<String>,
+    skipped_packages: Vec<String>,
+    strict_rules: bool,
+    absolute_paths: bool,
+    require_files: bool,
+    score_weights: BTreeMap<Criticity, f64>,
+    follow_symlinks: bool,
+    compare_report: Option<String>,
+    only_new: bool,
+    baseline: Option<String>,
+    baseline_generate: Option<String>,
+    max_findings: Option<usize>,
+    selector: Option<Selector>,
+    include_smali: bool,
+    include_generated: bool,
+    redact_secrets: bool,
+    normalize_newlines: bool,
+    manifest_analysis: bool,
+    certificate_analysis: bool,
+    code_analysis: bool,
+    exit_codes: BTreeMap<Criticity, i32>,
 }
 
 impl Config {
@@ -89,22 +166,37 @@ impl Config {
     }
 
     pub fn check(&self) -> bool {
-        file_exists(&self.downloads_folder) &&
-        file_exists(format!("{}/{}.apk", self.downloads_folder, self.app_id)) &&
+        (self.source_folder.is_some() ||
+         file_exists(&self.downloads_folder) &&
+         file_exists(format!("{}/{}.apk", self.downloads_folder, self.app_id))) &&
+        self.source_folder.as_ref().map_or(true, file_exists) &&
+        self.rule_overrides.as_ref().map_or(true, file_exists) &&
+        self.known_good.as_ref().map_or(true, file_exists) &&
         file_exists(&self.apktool_file) && file_exists(&self.dex2jar_folder) &&
         file_exists(&self.jd_cmd_file) && file_exists(&self.results_template) &&
-        file_exists(&self.rules_json)
+        self.get_rules_files().iter().all(|f| *f == "-" || file_exists(f)) &&
+        self.get_rules_files().iter().filter(|f| **f == "-").count() <= 1 &&
+        (self.output_dir.is_some() || self.report_formats.len() <= 1)
     }
 
     pub fn get_errors(&self) -> Vec<String> {
         let mut errors = Vec::new();
-        if !file_exists(&self.downloads_folder) {
-            errors.push(format!("the downloads folder `{}` does not exist",
-                                self.downloads_folder));
-        }
-        if !file_exists(format!("{}/{}.apk", self.downloads_folder, self.app_id)) {
-            errors.push(format!("the APK file `{}` does not exist",
-                                format!("{}/{}.apk", self.downloads_folder, self.app_id)));
+        match self.source_folder {
+            Some(ref folder) => {
+                if !file_exists(folder) {
+                    errors.push(format!("the source folder `{}` does not exist", folder));
+                }
+            }
+            None => {
+                if !file_exists(&self.downloads_folder) {
+                    errors.push(format!("the downloads folder `{}` does not exist",
+                                        self.downloads_folder));
+                }
+                if !file_exists(format!("{}/{}.apk", self.downloads_folder, self.app_id)) {
+                    errors.push(format!("the APK file `{}` does not exist",
+                                        format!("{}/{}.apk", self.downloads_folder, self.app_id)));
+                }
+            }
         }
         if !file_exists(&self.apktool_file) {
             errors.push(format!("the APKTool JAR file `{}` does not exist",
@@ -121,8 +213,27 @@ impl Config {
             errors.push(format!("the results template `{}` does not exist",
                                 self.results_template));
         }
-        if !file_exists(&self.rules_json) {
-            errors.push(format!("the `{}` rule file does not exist", self.rules_json));
+        if let Some(ref file) = self.rule_overrides {
+            if !file_exists(file) {
+                errors.push(format!("the rule overrides file `{}` does not exist", file));
+            }
+        }
+        if let Some(ref file) = self.known_good {
+            if !file_exists(file) {
+                errors.push(format!("the known-good hashes file `{}` does not exist", file));
+            }
+        }
+        for file in self.get_rules_files() {
+            if file != "-" && !file_exists(file) {
+                errors.push(format!("the `{}` rule file does not exist", file));
+            }
+        }
+        if self.get_rules_files().iter().filter(|f| **f == "-").count() > 1 {
+            errors.push(String::from("only one rule file can be read from the standard input"));
+        }
+        if self.output_dir.is_none() && self.report_formats.len() > 1 {
+            errors.push(String::from("several report formats can only be written to an output \
+                                      folder"));
         }
         errors
     }
@@ -155,6 +266,15 @@ impl Config {
         self.quiet = quiet;
     }
 
+    /// Checks if debug messages, such as the reasons why the rules are skipped, should be shown.
+    pub fn is_debug(&self) -> bool {
+        self.debug
+    }
+
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
     pub fn is_force(&self) -> bool {
         self.force
     }
@@ -175,6 +295,21 @@ impl Config {
         self.threads
     }
 
+    /// Sets the number of analysis threads. It must be at least 1.
+    pub fn set_threads(&mut self, threads: u8) {
+        self.threads = threads;
+    }
+
+    /// Checks if the code should be analyzed in the current thread, without spawning analysis
+    /// threads, so that the runs are deterministic and easier to debug.
+    pub fn is_single_thread(&self) -> bool {
+        self.single_thread
+    }
+
+    pub fn set_single_thread(&mut self, single_thread: bool) {
+        self.single_thread = single_thread;
+    }
+
     pub fn get_downloads_folder(&self) -> &str {
         self.downloads_folder.as_str()
     }
@@ -207,6 +342,478 @@ impl Config {
         self.rules_json.as_str()
     }
 
+    pub fn set_rules_json(&mut self, rules_json: &str) {
+        self.rules_json = String::from(rules_json);
+    }
+
+    /// Gets the files the rules will be loaded from, in order.
+    ///
+    /// If no rule files have been added, the rules will be loaded from the rules JSON file. A file
+    /// named `-` means that the rules will be read from the standard input.
+    pub fn get_rules_files(&self) -> Vec<&str> {
+        if self.rules_files.is_empty() {
+            vec![self.rules_json.as_str()]
+        } else {
+            self.rules_files.iter().map(|f| f.as_str()).collect()
+        }
+    }
+
+    pub fn add_rules_file(&mut self, file: &str) {
+        self.rules_files.push(String::from(file));
+    }
+
+    pub fn get_source_folder(&self) -> Option<&str> {
+        match self.source_folder.as_ref() {
+            Some(f) => Some(f.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_source_folder(&mut self, folder: &str) {
+        self.source_folder = Some(String::from(folder));
+    }
+
+    /// Gets the root folder of the code analysis.
+    ///
+    /// It will be the source folder if one was given, or the folder where the application was
+    /// decompiled otherwise.
+    pub fn get_analysis_folder(&self) -> String {
+        match self.source_folder {
+            Some(ref folder) => folder.clone(),
+            None => format!("{}/{}", self.dist_folder, self.app_id),
+        }
+    }
+
+    /// Gets the file with the criticity and confidence overrides for the rules, if any.
+    pub fn get_rule_overrides(&self) -> Option<&str> {
+        match self.rule_overrides.as_ref() {
+            Some(f) => Some(f.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_rule_overrides(&mut self, file: &str) {
+        self.rule_overrides = Some(String::from(file));
+    }
+
+    /// Gets the file with the SHA-256 hashes of the source files that are known to be safe, and
+    /// that will not be analyzed, if any.
+    pub fn get_known_good(&self) -> Option<&str> {
+        match self.known_good.as_ref() {
+            Some(f) => Some(f.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_known_good(&mut self, file: &str) {
+        self.known_good = Some(String::from(file));
+    }
+
+    /// Gets the extensions of the source files that will be analyzed.
+    pub fn get_extensions(&self) -> VecIter<String> {
+        self.extensions.iter()
+    }
+
+    pub fn add_extension(&mut self, extension: &str) {
+        let extension = extension.trim_left_matches('.');
+        if !self.extensions.iter().any(|e| e == extension) {
+            self.extensions.push(String::from(extension));
+        }
+    }
+
+    /// Gets the package path prefixes whose source files are not analyzed, such as
+    /// `com/google/android/gms`. They are relative to the `classes` and `smali` folders.
+    pub fn get_skipped_packages(&self) -> VecIter<String> {
+        self.skipped_packages.iter()
+    }
+
+    /// Skips the source files of the given package. Both `com.facebook` and `com/facebook` are
+    /// accepted.
+    pub fn skip_package(&mut self, package: &str) {
+        let package = package.trim_matches(|c| c == '/' || c == '.').replace('.', "/");
+        if !package.is_empty() && !self.skipped_packages.iter().any(|p| p == &package) {
+            self.skipped_packages.push(package);
+        }
+    }
+
+    /// Checks if the warnings found when loading the rules should abort the analysis.
+    pub fn is_strict_rules(&self) -> bool {
+        self.strict_rules
+    }
+
+    pub fn set_strict_rules(&mut self, strict_rules: bool) {
+        self.strict_rules = strict_rules;
+    }
+
+    /// Checks if the files of the findings should be reported with absolute paths, instead of
+    /// paths relative to the analysis folder.
+    pub fn is_absolute_paths(&self) -> bool {
+        self.absolute_paths
+    }
+
+    pub fn set_absolute_paths(&mut self, absolute_paths: bool) {
+        self.absolute_paths = absolute_paths;
+    }
+
+    /// Checks if the analysis should fail when there are no source files to analyze.
+    pub fn is_require_files(&self) -> bool {
+        self.require_files
+    }
+
+    pub fn set_require_files(&mut self, require_files: bool) {
+        self.require_files = require_files;
+    }
+
+    /// Checks if symbolic links should be followed when searching for the files to analyze.
+    pub fn is_follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) {
+        self.follow_symlinks = follow_symlinks;
+    }
+
+    /// Gets the previous JSON report to compare the findings with, if any.
+    pub fn get_compare_report(&self) -> Option<&str> {
+        match self.compare_report.as_ref() {
+            Some(r) => Some(r.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_compare_report(&mut self, report: &str) {
+        self.compare_report = Some(String::from(report));
+    }
+
+    /// Gets the baseline with the findings to suppress, if any.
+    pub fn get_baseline(&self) -> Option<&str> {
+        match self.baseline.as_ref() {
+            Some(b) => Some(b.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_baseline(&mut self, baseline: &str) {
+        self.baseline = Some(String::from(baseline));
+    }
+
+    /// Gets the path where a baseline with all the findings of the analysis should be written,
+    /// if any.
+    pub fn get_baseline_generate(&self) -> Option<&str> {
+        match self.baseline_generate.as_ref() {
+            Some(b) => Some(b.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_baseline_generate(&mut self, path: &str) {
+        self.baseline_generate = Some(String::from(path));
+    }
+
+    /// Checks if only the findings that were not in the compared report should be reported.
+    pub fn is_only_new(&self) -> bool {
+        self.only_new
+    }
+
+    pub fn set_only_new(&mut self, only_new: bool) {
+        self.only_new = only_new;
+    }
+
+    /// Gets the maximum number of findings to report, if any.
+    pub fn get_max_findings(&self) -> Option<usize> {
+        self.max_findings
+    }
+
+    pub fn set_max_findings(&mut self, max_findings: usize) {
+        self.max_findings = Some(max_findings);
+    }
+
+    /// Gets the selector of the findings to report, if only some of them must be reported.
+    pub fn get_selector(&self) -> Option<&Selector> {
+        self.selector.as_ref()
+    }
+
+    pub fn set_selector(&mut self, selector: Selector) {
+        self.selector = Some(selector);
+    }
+
+    /// Checks if the smali code of the application should be analyzed too.
+    pub fn is_include_smali(&self) -> bool {
+        self.include_smali
+    }
+
+    pub fn set_include_smali(&mut self, include_smali: bool) {
+        self.include_smali = include_smali;
+    }
+
+    /// Checks if the generated `BuildConfig.java` files, and the files in `databinding` and
+    /// `generated` folders, should be analyzed too.
+    pub fn is_include_generated(&self) -> bool {
+        self.include_generated
+    }
+
+    pub fn set_include_generated(&mut self, include_generated: bool) {
+        self.include_generated = include_generated;
+    }
+
+    /// Checks if the secrets found by the rules, such as keys, should be masked in the code of
+    /// the findings, so that the reports can be shared.
+    pub fn is_redact_secrets(&self) -> bool {
+        self.redact_secrets
+    }
+
+    pub fn set_redact_secrets(&mut self, redact_secrets: bool) {
+        self.redact_secrets = redact_secrets;
+    }
+
+    /// Checks if the CRLF line endings of the source files should be converted to LF before
+    /// analyzing them, so that the rules that match the end of the lines work in files written in
+    /// Windows. The lines and code of the findings then refer to the normalized files.
+    pub fn is_normalize_newlines(&self) -> bool {
+        self.normalize_newlines
+    }
+
+    pub fn set_normalize_newlines(&mut self, normalize_newlines: bool) {
+        self.normalize_newlines = normalize_newlines;
+    }
+
+    /// Checks if the manifest should be analyzed. If not, the code is analyzed without it, so the
+    /// rules that require permissions never match.
+    pub fn is_manifest_analysis_enabled(&self) -> bool {
+        self.manifest_analysis
+    }
+
+    pub fn set_manifest_analysis_enabled(&mut self, manifest_analysis: bool) {
+        self.manifest_analysis = manifest_analysis;
+    }
+
+    /// Checks if the certificates of the application should be analyzed.
+    pub fn is_certificate_analysis_enabled(&self) -> bool {
+        self.certificate_analysis
+    }
+
+    pub fn set_certificate_analysis_enabled(&mut self, certificate_analysis: bool) {
+        self.certificate_analysis = certificate_analysis;
+    }
+
+    /// Checks if the source code of the application should be analyzed with the code rules.
+    pub fn is_code_analysis_enabled(&self) -> bool {
+        self.code_analysis
+    }
+
+    pub fn set_code_analysis_enabled(&mut self, code_analysis: bool) {
+        self.code_analysis = code_analysis;
+    }
+
+    /// Gets the exit code to use when the given criticity is the highest one of the findings.
+    ///
+    /// There are no exit codes configured by default, so the analyzer exits with 0 whatever it
+    /// finds.
+    pub fn get_exit_code(&self, criticity: Criticity) -> Option<i32> {
+        self.exit_codes.get(&criticity).cloned()
+    }
+
+    pub fn set_exit_code(&mut self, criticity: Criticity, code: i32) {
+        self.exit_codes.insert(criticity, code);
+    }
+
+    /// Gets the weight of each criticity in the score of the application.
+    pub fn get_score_weights(&self) -> &BTreeMap<Criticity, f64> {
+        &self.score_weights
+    }
+
+    pub fn set_score_weight(&mut self, criticity: Criticity, weight: f64) {
+        self.score_weights.insert(criticity, weight);
+    }
+
+    pub fn get_min_confidence(&self) -> Confidence {
+        self.min_confidence
+    }
+
+    pub fn set_min_confidence(&mut self, confidence: Confidence) {
+        self.min_confidence = confidence;
+    }
+
+    pub fn get_output_json_gz(&self) -> Option<&str> {
+        match self.output_json_gz.as_ref() {
+            Some(p) => Some(p.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_output_json_gz(&mut self, path: &str) {
+        self.output_json_gz = Some(String::from(path));
+    }
+
+    /// Gets the path where the text report should be written, if any.
+    pub fn get_output_txt(&self) -> Option<&str> {
+        match self.output_txt.as_ref() {
+            Some(p) => Some(p.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_output_txt(&mut self, path: &str) {
+        self.output_txt = Some(String::from(path));
+    }
+
+    /// Gets the path where the JSON report with the findings grouped by file should be written,
+    /// if any.
+    pub fn get_output_by_file(&self) -> Option<&str> {
+        match self.output_by_file.as_ref() {
+            Some(p) => Some(p.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_output_by_file(&mut self, path: &str) {
+        self.output_by_file = Some(String::from(path));
+    }
+
+    /// Gets the command to run once the report is generated, if any, as the program followed by
+    /// its arguments.
+    pub fn get_post_hook(&self) -> Option<&[String]> {
+        match self.post_hook.as_ref() {
+            Some(h) => Some(&h[..]),
+            None => None,
+        }
+    }
+
+    pub fn set_post_hook<I: IntoIterator<Item = String>>(&mut self, command: I) {
+        let command: Vec<_> = command.into_iter().collect();
+        self.post_hook = if command.is_empty() {
+            None
+        } else {
+            Some(command)
+        };
+    }
+
+    /// Gets the formats of the report to write to the output or to the output folder. Only one
+    /// format can be written to the output.
+    pub fn get_report_formats(&self) -> VecIter<ReportFormat> {
+        self.report_formats.iter()
+    }
+
+    /// Sets the formats of the report, replacing the ones of the configuration files.
+    pub fn set_report_formats<I: IntoIterator<Item = ReportFormat>>(&mut self, formats: I) {
+        self.report_formats.clear();
+        for format in formats {
+            if !self.report_formats.contains(&format) {
+                self.report_formats.push(format);
+            }
+        }
+    }
+
+    /// Gets the file where the report will be written in the selected format. If there is no
+    /// output file, the report will be written to the standard output.
+    pub fn get_output(&self) -> Option<&str> {
+        match self.output.as_ref() {
+            Some(p) => Some(p.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_output(&mut self, path: &str) {
+        self.output = Some(String::from(path));
+    }
+
+    /// Gets the folder where the report will be written in each of the selected formats, or in
+    /// all of them if none was selected, with stable file names such as `report.sarif`.
+    pub fn get_output_dir(&self) -> Option<&str> {
+        match self.output_dir.as_ref() {
+            Some(p) => Some(p.as_str()),
+            None => None,
+        }
+    }
+
+    pub fn set_output_dir(&mut self, path: &str) {
+        self.output_dir = Some(String::from(path));
+    }
+
+    /// Checks if the report in the selected format is written to the standard output, since
+    /// there is no output file or folder to write it to.
+    pub fn is_report_to_stdout(&self) -> bool {
+        !self.report_formats.is_empty() && self.output.is_none() && self.output_dir.is_none()
+    }
+
+    /// Gets the language of the human-readable texts of the reports, such as the section headers.
+    pub fn get_lang(&self) -> Lang {
+        self.lang
+    }
+
+    pub fn set_lang(&mut self, lang: Lang) {
+        self.lang = lang;
+    }
+
+    pub fn get_enabled_categories(&self) -> VecIter<String> {
+        self.enabled_categories.iter()
+    }
+
+    pub fn enable_category(&mut self, category: &str) {
+        self.enabled_categories.push(String::from(category));
+    }
+
+    pub fn get_disabled_categories(&self) -> VecIter<String> {
+        self.disabled_categories.iter()
+    }
+
+    pub fn disable_category(&mut self, category: &str) {
+        self.disabled_categories.push(String::from(category));
+    }
+
+    /// Gets the rule tags that were explicitly included, so that the rules with tags excluded by
+    /// default, such as `experimental`, run too.
+    pub fn get_included_tags(&self) -> VecIter<String> {
+        self.included_tags.iter()
+    }
+
+    pub fn include_tag(&mut self, tag: &str) {
+        self.included_tags.push(String::from(tag));
+    }
+
+    /// Gets the rule tags whose rules should not run.
+    pub fn get_excluded_tags(&self) -> VecIter<String> {
+        self.excluded_tags.iter()
+    }
+
+    pub fn exclude_tag(&mut self, tag: &str) {
+        self.excluded_tags.push(String::from(tag));
+    }
+
+    pub fn get_cache_folder(&self) -> &str {
+        self.cache_folder.as_str()
+    }
+
+    pub fn set_cache_folder(&mut self, cache_folder: &str) {
+        self.cache_folder = String::from(cache_folder);
+    }
+
+    pub fn is_cache_enabled(&self) -> bool {
+        self.cache
+    }
+
+    pub fn set_cache_enabled(&mut self, cache: bool) {
+        self.cache = cache;
+    }
+
+    pub fn get_file_timeout(&self) -> Option<Duration> {
+        self.file_timeout
+    }
+
+    pub fn set_file_timeout(&mut self, timeout: Duration) {
+        self.file_timeout = Some(timeout);
+    }
+
+    /// Gets the size in bytes above which the source files are memory-mapped instead of being
+    /// read, if any.
+    pub fn get_mmap_threshold(&self) -> Option<u64> {
+        self.mmap_threshold
+    }
+
+    pub fn set_mmap_threshold(&mut self, threshold: u64) {
+        self.mmap_threshold = Some(threshold);
+    }
+
     pub fn get_unknown_permission_criticity(&self) -> Criticity {
         self.unknown_permission.0
     }
@@ -281,6 +888,16 @@ impl Config {
                         }
                     }
                 }
+                "cache_folder" => {
+                    match value {
+                        Value::String(s) => config.cache_folder = s,
+                        _ => {
+                            print_warning("The 'cache_folder' option in config.toml must be an \
+                                           string.\nUsing default.",
+                                          verbose)
+                        }
+                    }
+                }
                 "apktool_file" => {
                     match value {
                         Value::String(s) => {
@@ -358,6 +975,93 @@ impl Config {
                         }
                     }
                 }
+                "rule_overrides" => {
+                    match value {
+                        Value::String(s) => config.rule_overrides = Some(s),
+                        _ => {
+                            print_warning("The 'rule_overrides' option in config.toml must be an \
+                                           string.\nIgnoring it.",
+                                          verbose)
+                        }
+                    }
+                }
+                "score_weights" => {
+                    match value {
+                        Value::Table(weights) => {
+                            for (name, weight) in weights {
+                                let criticity = match Criticity::from_str(name.as_str()) {
+                                    Ok(c) => c,
+                                    Err(_) => {
+                                        print_warning(format!("Unknown criticity {} in the \
+                                                               'score_weights' option of \
+                                                               config.toml.\nIgnoring it.",
+                                                              name.italic()),
+                                                      verbose);
+                                        continue;
+                                    }
+                                };
+                                match weight {
+                                    Value::Float(w) if w >= 0_f64 => {
+                                        config.set_score_weight(criticity, w)
+                                    }
+                                    Value::Integer(w) if w >= 0 => {
+                                        config.set_score_weight(criticity, w as f64)
+                                    }
+                                    _ => {
+                                        print_warning(format!("The weight of the {} criticity \
+                                                               in config.toml must be a \
+                                                               positive number.\nUsing \
+                                                               default.",
+                                                              name.italic()),
+                                                      verbose)
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            print_warning("The 'score_weights' option in config.toml must be a \
+                                           table.\nUsing default.",
+                                          verbose)
+                        }
+                    }
+                }
+                "exit_codes" => {
+                    match value {
+                        Value::Table(codes) => {
+                            for (name, code) in codes {
+                                let criticity = match Criticity::from_str(name.as_str()) {
+                                    Ok(c) => c,
+                                    Err(_) => {
+                                        print_warning(format!("Unknown criticity {} in the \
+                                                               'exit_codes' option of \
+                                                               config.toml.\nIgnoring it.",
+                                                              name.italic()),
+                                                      verbose);
+                                        continue;
+                                    }
+                                };
+                                match code {
+                                    Value::Integer(c) if c >= 0 && c <= 255 => {
+                                        config.set_exit_code(criticity, c as i32)
+                                    }
+                                    _ => {
+                                        print_warning(format!("The exit code of the {} \
+                                                               criticity in config.toml must be \
+                                                               a number between 0 and 255.\n\
+                                                               Ignoring it.",
+                                                              name.italic()),
+                                                      verbose)
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            print_warning("The 'exit_codes' option in config.toml must be a \
+                                           table.\nUsing default.",
+                                          verbose)
+                        }
+                    }
+                }
                 "permissions" => {
                     match value {
                         Value::Array(p) => {
@@ -484,6 +1188,295 @@ impl Config {
         }
         Ok(())
     }
+
+    /// Loads the analysis options of the `super.toml` file of the working directory, and then
+    /// the ones of the `super.toml` file of the given application root, if they exist.
+    ///
+    /// These files hold the options of the analysis of a project, so that they do not have to
+    /// be given in the command line each time. They must be loaded before applying the command
+    /// line options, so that these override them. The file of the application root comes from
+    /// the analyzed application, so only the options in `APP_ROOT_OPTIONS` are taken from it.
+    pub fn load_project_files(&mut self, app_root: Option<&str>) -> Result<()> {
+        let mut files = vec![(PathBuf::from(PROJECT_FILE), false)];
+        if let Some(root) = app_root {
+            let file = Path::new(root).join(PROJECT_FILE);
+            let same = match (fs::canonicalize(&files[0].0), fs::canonicalize(&file)) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => false,
+            };
+            if !same {
+                files.push((file, true));
+            }
+        }
+        for (file, app_root) in files {
+            if file.is_file() {
+                try!(self.read_project_file(&file, app_root));
+                self.loaded_files.push(file.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the analysis options of the given `super.toml` file.
+    ///
+    /// Options with an invalid value are ignored with a warning, and the ones given as arrays,
+    /// such as the rule files, are added to the current ones. Relative paths are resolved
+    /// against the folder of the file.
+    pub fn load_project_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.read_project_file(path, false)
+    }
+
+    /// Loads the analysis options of the given `super.toml` file, only taking the ones in
+    /// `APP_ROOT_OPTIONS` if it is the file of the analyzed application.
+    fn read_project_file<P: AsRef<Path>>(&mut self, path: P, app_root: bool) -> Result<()> {
+        let folder = path.as_ref().parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        let resolve = |file: String| folder.join(file).to_string_lossy().into_owned();
+        let mut f = try!(fs::File::open(path.as_ref()));
+        let mut toml = String::new();
+        try!(f.read_to_string(&mut toml));
+
+        let verbose = self.verbose;
+        let mut parser = Parser::new(toml.as_str());
+        let toml = match parser.parse() {
+            Some(t) => t,
+            None => {
+                print_error(format!("There was an error parsing the {} file: {:?}",
+                                    PROJECT_FILE,
+                                    parser.errors),
+                            verbose);
+                return Err(Error::ParseError);
+            }
+        };
+
+        for (key, value) in toml {
+            if app_root && PROJECT_OPTIONS.contains(&key.as_str()) &&
+               !APP_ROOT_OPTIONS.contains(&key.as_str()) {
+                print_warning(format!("The '{}' option cannot be set in the {} file of the \
+                                       analyzed application.\nIgnoring it.",
+                                      key,
+                                      PROJECT_FILE),
+                              verbose);
+                continue;
+            }
+            let valid = match (key.as_str(), value) {
+                ("threads", Value::Integer(t)) if t >= 1 && t <= MAX_THREADS => {
+                    self.threads = t as u8;
+                    true
+                }
+                ("single_thread", Value::Boolean(b)) => {
+                    self.single_thread = b;
+                    true
+                }
+                ("min_confidence", Value::String(ref c)) => {
+                    match Confidence::from_str(c) {
+                        Ok(c) => {
+                            self.min_confidence = c;
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                }
+                ("format", Value::String(ref f)) => {
+                    match ReportFormat::from_str(f) {
+                        Ok(f) => {
+                            self.set_report_formats(vec![f]);
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                }
+                ("format", Value::Array(ref a)) => {
+                    let formats: Vec<_> = a.iter()
+                        .filter_map(|v| v.as_str())
+                        .filter_map(|f| ReportFormat::from_str(f).ok())
+                        .collect();
+                    if formats.len() == a.len() {
+                        self.set_report_formats(formats);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                ("output", Value::String(o)) => {
+                    self.output = Some(resolve(o));
+                    true
+                }
+                ("output_dir", Value::String(o)) => {
+                    self.output_dir = Some(resolve(o));
+                    true
+                }
+                ("lang", Value::String(ref l)) => {
+                    match Lang::from_str(l) {
+                        Ok(l) => {
+                            self.lang = l;
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                }
+                ("output_json_gz", Value::String(o)) => {
+                    self.output_json_gz = Some(resolve(o));
+                    true
+                }
+                ("output_txt", Value::String(o)) => {
+                    self.output_txt = Some(resolve(o));
+                    true
+                }
+                ("output_by_file", Value::String(o)) => {
+                    self.output_by_file = Some(resolve(o));
+                    true
+                }
+                ("baseline", Value::String(b)) => {
+                    self.baseline = Some(resolve(b));
+                    true
+                }
+                ("post_hook", Value::String(h)) => {
+                    self.set_post_hook(vec![h]);
+                    true
+                }
+                ("post_hook", Value::Array(ref a)) => {
+                    let command: Vec<_> = a.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(String::from)
+                        .collect();
+                    if !command.is_empty() && command.len() == a.len() {
+                        self.set_post_hook(command);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                ("rule_overrides", Value::String(o)) => {
+                    self.rule_overrides = Some(resolve(o));
+                    true
+                }
+                ("known_good", Value::String(k)) => {
+                    self.known_good = Some(resolve(k));
+                    true
+                }
+                ("file_timeout", Value::Integer(t)) if t > 0 => {
+                    self.file_timeout = Some(Duration::from_secs(t as u64));
+                    true
+                }
+                ("mmap_threshold", Value::Integer(t)) if t >= 0 => {
+                    self.mmap_threshold = Some(t as u64);
+                    true
+                }
+                ("max_findings", Value::Integer(m)) if m > 0 => {
+                    self.max_findings = Some(m as usize);
+                    true
+                }
+                ("select", Value::String(ref s)) => {
+                    match Selector::new(s) {
+                        Ok(s) => {
+                            self.selector = Some(s);
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                }
+                ("cache", Value::Boolean(b)) => {
+                    self.cache = b;
+                    true
+                }
+                ("strict_rules", Value::Boolean(b)) => {
+                    self.strict_rules = b;
+                    true
+                }
+                ("absolute_paths", Value::Boolean(b)) => {
+                    self.absolute_paths = b;
+                    true
+                }
+                ("require_files", Value::Boolean(b)) => {
+                    self.require_files = b;
+                    true
+                }
+                ("follow_symlinks", Value::Boolean(b)) => {
+                    self.follow_symlinks = b;
+                    true
+                }
+                ("include_smali", Value::Boolean(b)) => {
+                    self.include_smali = b;
+                    true
+                }
+                ("include_generated", Value::Boolean(b)) => {
+                    self.include_generated = b;
+                    true
+                }
+                ("redact_secrets", Value::Boolean(b)) => {
+                    self.redact_secrets = b;
+                    true
+                }
+                ("normalize_newlines", Value::Boolean(b)) => {
+                    self.normalize_newlines = b;
+                    true
+                }
+                ("manifest_analysis", Value::Boolean(b)) => {
+                    self.manifest_analysis = b;
+                    true
+                }
+                ("certificate_analysis", Value::Boolean(b)) => {
+                    self.certificate_analysis = b;
+                    true
+                }
+                ("code_analysis", Value::Boolean(b)) => {
+                    self.code_analysis = b;
+                    true
+                }
+                ("rules", Value::Array(ref a)) |
+                ("extensions", Value::Array(ref a)) |
+                ("skip_packages", Value::Array(ref a)) |
+                ("enable_categories", Value::Array(ref a)) |
+                ("disable_categories", Value::Array(ref a)) |
+                ("include_tags", Value::Array(ref a)) |
+                ("exclude_tags", Value::Array(ref a)) => {
+                    let values: Vec<_> = a.iter().filter_map(|v| v.as_str()).collect();
+                    if values.len() == a.len() {
+                        for value in values {
+                            match key.as_str() {
+                                // The standard input is not resolved as a file.
+                                "rules" if value == "-" => self.add_rules_file(value),
+                                "rules" => self.add_rules_file(&resolve(String::from(value))),
+                                "extensions" => self.add_extension(value),
+                                "skip_packages" => self.skip_package(value),
+                                "enable_categories" => self.enable_category(value),
+                                "disable_categories" => self.disable_category(value),
+                                "include_tags" => self.include_tag(value),
+                                _ => self.exclude_tag(value),
+                            }
+                        }
+                        true
+                    } else {
+                        false
+                    }
+                }
+                (option, _) if PROJECT_OPTIONS.contains(&option) => false,
+                _ => {
+                    print_warning(format!("Unknown option {} in {}.", key, PROJECT_FILE),
+                                  verbose);
+                    true
+                }
+            };
+            if !valid {
+                print_warning(format!("The '{}' option in {} is not valid.\nIgnoring it.",
+                                      key,
+                                      PROJECT_FILE),
+                              verbose);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Gets the default weights of each criticity in the score of the application.
+fn default_score_weights() -> BTreeMap<Criticity, f64> {
+    let mut weights = BTreeMap::new();
+    weights.insert(Criticity::Warning, 0.5_f64);
+    weights.insert(Criticity::Low, 1_f64);
+    weights.insert(Criticity::Medium, 2_f64);
+    weights.insert(Criticity::High, 5_f64);
+    weights.insert(Criticity::Critical, 10_f64);
+    weights
 }
 
 impl Default for Config {
@@ -494,9 +1487,11 @@ impl Default for Config {
                 app_id: String::new(),
                 verbose: false,
                 quiet: false,
+                debug: false,
                 force: false,
                 bench: false,
                 threads: 2,
+                single_thread: false,
                 downloads_folder: String::from("downloads"),
                 dist_folder: String::from("dist"),
                 results_folder: String::from("results"),
@@ -515,15 +1510,60 @@ impl Default for Config {
                                                    lead to missunderstanding between developers.")),
                 permissions: BTreeSet::new(),
                 loaded_files: Vec::new(),
+                min_confidence: Confidence::Low,
+                output_json_gz: None,
+                output_txt: None,
+                output_by_file: None,
+                post_hook: None,
+                report_formats: Vec::new(),
+                output: None,
+                output_dir: None,
+                lang: Lang::En,
+                enabled_categories: Vec::new(),
+                disabled_categories: Vec::new(),
+                included_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                cache_folder: String::from("cache"),
+                cache: true,
+                file_timeout: None,
+                mmap_threshold: None,
+                rules_files: Vec::new(),
+                source_folder: None,
+                rule_overrides: None,
+                known_good: None,
+                extensions: vec![String::from("xml"), String::from("java")],
+                skipped_packages: vec![String::from("android"),
+                                       String::from("com/google/android/gms")],
+                strict_rules: false,
+                absolute_paths: false,
+                require_files: false,
+                score_weights: default_score_weights(),
+                follow_symlinks: false,
+                compare_report: None,
+                only_new: false,
+                baseline: None,
+                baseline_generate: None,
+                max_findings: None,
+                selector: None,
+                include_smali: false,
+                include_generated: false,
+                redact_secrets: false,
+                normalize_newlines: false,
+                manifest_analysis: true,
+                certificate_analysis: true,
+                code_analysis: true,
+                exit_codes: BTreeMap::new(),
             }
         } else {
             Config {
                 app_id: String::new(),
                 verbose: false,
                 quiet: false,
+                debug: false,
                 force: false,
                 bench: false,
                 threads: 2,
+                single_thread: false,
                 downloads_folder: String::from("downloads"),
                 dist_folder: String::from("dist"),
                 results_folder: String::from("results"),
@@ -542,6 +1582,49 @@ impl Default for Config {
                                                    lead to missunderstanding between developers.")),
                 permissions: BTreeSet::new(),
                 loaded_files: Vec::new(),
+                min_confidence: Confidence::Low,
+                output_json_gz: None,
+                output_txt: None,
+                output_by_file: None,
+                post_hook: None,
+                report_formats: Vec::new(),
+                output: None,
+                output_dir: None,
+                lang: Lang::En,
+                enabled_categories: Vec::new(),
+                disabled_categories: Vec::new(),
+                included_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                cache_folder: String::from("cache"),
+                cache: true,
+                file_timeout: None,
+                mmap_threshold: None,
+                rules_files: Vec::new(),
+                source_folder: None,
+                rule_overrides: None,
+                known_good: None,
+                extensions: vec![String::from("xml"), String::from("java")],
+                skipped_packages: vec![String::from("android"),
+                                       String::from("com/google/android/gms")],
+                strict_rules: false,
+                absolute_paths: false,
+                require_files: false,
+                score_weights: default_score_weights(),
+                follow_symlinks: false,
+                compare_report: None,
+                only_new: false,
+                baseline: None,
+                baseline_generate: None,
+                max_findings: None,
+                selector: None,
+                include_smali: false,
+                include_generated: false,
+                redact_secrets: false,
+                normalize_newlines: false,
+                manifest_analysis: true,
+                certificate_analysis: true,
+                code_analysis: true,
+                exit_codes: BTreeMap::new(),
             }
         }
     }
@@ -553,9 +1636,11 @@ impl Default for Config {
                 app_id: String::new(),
                 verbose: false,
                 quiet: false,
+                debug: false,
                 force: false,
                 bench: false,
                 threads: 2,
+                single_thread: false,
                 downloads_folder: String::from("downloads"),
                 dist_folder: String::from("dist"),
                 results_folder: String::from("results"),
@@ -574,15 +1659,60 @@ impl Default for Config {
                                                    lead to missunderstanding between developers.")),
                 permissions: BTreeSet::new(),
                 loaded_files: Vec::new(),
+                min_confidence: Confidence::Low,
+                output_json_gz: None,
+                output_txt: None,
+                output_by_file: None,
+                post_hook: None,
+                report_formats: Vec::new(),
+                output: None,
+                output_dir: None,
+                lang: Lang::En,
+                enabled_categories: Vec::new(),
+                disabled_categories: Vec::new(),
+                included_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                cache_folder: String::from("cache"),
+                cache: true,
+                file_timeout: None,
+                mmap_threshold: None,
+                rules_files: Vec::new(),
+                source_folder: None,
+                rule_overrides: None,
+                known_good: None,
+                extensions: vec![String::from("xml"), String::from("java")],
+                skipped_packages: vec![String::from("android"),
+                                       String::from("com/google/android/gms")],
+                strict_rules: false,
+                absolute_paths: false,
+                require_files: false,
+                score_weights: default_score_weights(),
+                follow_symlinks: false,
+                compare_report: None,
+                only_new: false,
+                baseline: None,
+                baseline_generate: None,
+                max_findings: None,
+                selector: None,
+                include_smali: false,
+                include_generated: false,
+                redact_secrets: false,
+                normalize_newlines: false,
+                manifest_analysis: true,
+                certificate_analysis: true,
+                code_analysis: true,
+                exit_codes: BTreeMap::new(),
             }
         } else {
             Config {
                 app_id: String::new(),
                 verbose: false,
                 quiet: false,
+                debug: false,
                 force: false,
                 bench: false,
                 threads: 2,
+                single_thread: false,
                 downloads_folder: String::from("downloads"),
                 dist_folder: String::from("dist"),
                 results_folder: String::from("results"),
@@ -601,6 +1731,49 @@ impl Default for Config {
                                                    lead to missunderstanding between developers.")),
                 permissions: BTreeSet::new(),
                 loaded_files: Vec::new(),
+                min_confidence: Confidence::Low,
+                output_json_gz: None,
+                output_txt: None,
+                output_by_file: None,
+                post_hook: None,
+                report_formats: Vec::new(),
+                output: None,
+                output_dir: None,
+                lang: Lang::En,
+                enabled_categories: Vec::new(),
+                disabled_categories: Vec::new(),
+                included_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                cache_folder: String::from("cache"),
+                cache: true,
+                file_timeout: None,
+                mmap_threshold: None,
+                rules_files: Vec::new(),
+                source_folder: None,
+                rule_overrides: None,
+                known_good: None,
+                extensions: vec![String::from("xml"), String::from("java")],
+                skipped_packages: vec![String::from("android"),
+                                       String::from("com/google/android/gms")],
+                strict_rules: false,
+                absolute_paths: false,
+                require_files: false,
+                score_weights: default_score_weights(),
+                follow_symlinks: false,
+                compare_report: None,
+                only_new: false,
+                baseline: None,
+                baseline_generate: None,
+                max_findings: None,
+                selector: None,
+                include_smali: false,
+                include_generated: false,
+                redact_secrets: false,
+                normalize_newlines: false,
+                manifest_analysis: true,
+                certificate_analysis: true,
+                code_analysis: true,
+                exit_codes: BTreeMap::new(),
             }
         }
     }
@@ -611,9 +1784,11 @@ impl Default for Config {
             app_id: String::new(),
             verbose: false,
             quiet: false,
+            debug: false,
             force: false,
             bench: false,
             threads: 2,
+            single_thread: false,
             downloads_folder: String::from("downloads"),
             dist_folder: String::from("dist"),
             results_folder: String::from("results"),
@@ -628,11 +1803,54 @@ impl Default for Config {
                                                to missunderstanding between developers.")),
             permissions: BTreeSet::new(),
             loaded_files: Vec::new(),
+            min_confidence: Confidence::Low,
+            output_json_gz: None,
+            output_txt: None,
+            output_by_file: None,
+            post_hook: None,
+            report_formats: Vec::new(),
+            output: None,
+            output_dir: None,
+            lang: Lang::En,
+            enabled_categories: Vec::new(),
+            disabled_categories: Vec::new(),
+            included_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            cache_folder: String::from("cache"),
+            cache: true,
+            file_timeout: None,
+            mmap_threshold: None,
+            rules_files: Vec::new(),
+            source_folder: None,
+            rule_overrides: None,
+            known_good: None,
+            extensions: vec![String::from("xml"), String::from("java")],
+            skipped_packages: vec![String::from("android"),
+                                   String::from("com/google/android/gms")],
+            strict_rules: false,
+            absolute_paths: false,
+            require_files: false,
+            score_weights: default_score_weights(),
+            follow_symlinks: false,
+            compare_report: None,
+            only_new: false,
+            baseline: None,
+            baseline_generate: None,
+            max_findings: None,
+            selector: None,
+            include_smali: false,
+            include_generated: false,
+            redact_secrets: false,
+            normalize_newlines: false,
+            manifest_analysis: true,
+            certificate_analysis: true,
+            code_analysis: true,
+            exit_codes: BTreeMap::new(),
         }
     }
 }
 
-#[derive(Debug, Ord, Eq)]
+#[derive(Debug, Clone, Ord, Eq)]
 pub struct PermissionConfig {
     permission: Permission,
     criticity: Criticity,
@@ -691,8 +1909,9 @@ impl PermissionConfig {
 
 #[cfg(test)]
 mod tests {
-    use {Criticity, file_exists};
+    use {Criticity, Confidence, file_exists};
     use static_analysis::manifest::Permission;
+    use results::{Lang, ReportFormat};
     use super::Config;
     use std::fs;
     use std::path::Path;
@@ -705,6 +1924,7 @@ mod tests {
 
         assert_eq!(config.get_app_id(), "");
         assert!(!config.is_verbose());
+        assert!(!config.is_debug());
         assert!(!config.is_quiet());
         assert!(!config.is_force());
         assert!(!config.is_bench());
@@ -746,6 +1966,36 @@ mod tests {
                    "Even if the application can create its own permissions, it's discouraged, \
                     since it can lead to missunderstanding between developers.");
         assert_eq!(config.get_permissions().next(), None);
+        assert_eq!(config.get_min_confidence(), Confidence::Low);
+        assert_eq!(config.get_output_json_gz(), None);
+        assert_eq!(config.get_output_txt(), None);
+        assert_eq!(config.get_output_by_file(), None);
+        assert_eq!(config.get_baseline(), None);
+        assert_eq!(config.get_baseline_generate(), None);
+        assert_eq!(config.get_report_formats().next(), None);
+        assert_eq!(config.get_output(), None);
+        assert_eq!(config.get_output_dir(), None);
+        assert_eq!(config.get_lang(), Lang::En);
+        assert!(config.get_selector().is_none());
+        assert_eq!(config.get_enabled_categories().next(), None);
+        assert_eq!(config.get_disabled_categories().next(), None);
+        assert_eq!(config.get_cache_folder(), "cache");
+        assert!(config.is_cache_enabled());
+        assert_eq!(config.get_file_timeout(), None);
+        assert_eq!(config.get_mmap_threshold(), None);
+        assert!(!config.is_normalize_newlines());
+        assert!(config.is_manifest_analysis_enabled());
+        assert!(config.is_certificate_analysis_enabled());
+        assert!(config.is_code_analysis_enabled());
+        assert_eq!(config.get_skipped_packages().collect::<Vec<_>>(),
+                   vec!["android", "com/google/android/gms"]);
+        assert_eq!(config.get_rules_files(), vec![config.get_rules_json()]);
+        assert_eq!(config.get_extensions().collect::<Vec<_>>(), vec!["xml", "java"]);
+        assert_eq!(config.get_score_weights().get(&Criticity::Critical), Some(&10_f64));
+        assert_eq!(config.get_score_weights().get(&Criticity::Warning), Some(&0.5_f64));
+        assert_eq!(config.get_source_folder(), None);
+        assert_eq!(config.get_rule_overrides(), None);
+        assert_eq!(config.get_known_good(), None);
 
         if !file_exists(config.get_downloads_folder()) {
             fs::create_dir(config.get_downloads_folder()).unwrap();
@@ -846,4 +2096,49 @@ mod tests {
         fs::rename("config.toml", "config.toml.sample").unwrap();
         fs::rename("config.toml.bk", "config.toml").unwrap();
     }
+
+    #[test]
+    fn it_rules_files() {
+        let mut config: Config = Default::default();
+        config.add_rules_file("rules.json");
+        config.add_rules_file("-");
+        assert_eq!(config.get_rules_files(), vec!["rules.json", "-"]);
+        assert!(!config.get_errors().iter().any(|e| e.contains("rule")));
+
+        config.add_rules_file("-");
+        config.add_rules_file("test_missing_rules.json");
+        let errors = config.get_errors();
+        assert!(errors.contains(&String::from("only one rule file can be read from the standard \
+                                               input")));
+        assert!(errors.contains(&String::from("the `test_missing_rules.json` rule file does not \
+                                               exist")));
+        assert!(!config.check());
+    }
+
+    #[test]
+    fn it_report_formats_output_dir() {
+        let mut config: Config = Default::default();
+        config.set_report_formats(vec![ReportFormat::Json]);
+        assert!(!config.get_errors().iter().any(|e| e.contains("report formats")));
+
+        config.set_report_formats(vec![ReportFormat::Json, ReportFormat::Sarif]);
+        assert!(config.get_errors().contains(&String::from("several report formats can only be \
+                                                            written to an output folder")));
+        assert!(!config.check());
+
+        config.set_output_dir("test_report_formats_output_dir");
+        assert!(!config.get_errors().iter().any(|e| e.contains("report formats")));
+    }
+
+    #[test]
+    fn it_report_to_stdout() {
+        let mut config: Config = Default::default();
+        assert!(!config.is_report_to_stdout());
+
+        config.set_report_formats(vec![ReportFormat::Json]);
+        assert!(config.is_report_to_stdout());
+
+        config.set_output("test_report_to_stdout.json");
+        assert!(!config.is_report_to_stdout());
+    }
 }
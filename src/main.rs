@@ -11,22 +11,26 @@ extern crate toml;
 extern crate regex;
 extern crate crypto;
 extern crate rustc_serialize;
+extern crate flate2;
+extern crate memmap;
 
 mod decompilation;
 mod static_analysis;
 mod results;
 mod config;
 mod utils;
+mod watch;
 
-use std::{fs, io, fmt, result};
+use std::{fs, io, fmt, result, cmp};
 use std::path::Path;
 use std::fmt::Display;
 use std::str::FromStr;
 use std::error::Error as StdError;
 use std::io::Write;
-use std::process::exit;
+use std::process::{Command, ExitStatus, exit};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
+use std::sync::Arc;
 
 use serde::ser::{Serialize, Serializer};
 use serde_json::error::ErrorCode as JSONErrorCode;
@@ -35,7 +39,9 @@ use colored::Colorize;
 
 use decompilation::*;
 use static_analysis::*;
+use static_analysis::code::{Rule, load_code_rules, get_rules_dir_files, explain_rule, test_rules};
 use results::*;
+use watch::{PollingWatcher, watch};
 pub use config::Config;
 pub use utils::*;
 
@@ -44,19 +50,31 @@ static BANNER: &'static str = include_str!("banner.txt");
 fn main() {
     let matches = get_help_menu();
 
-    let app_id = matches.value_of("package").unwrap();
+    if matches.is_present("rules_schema") {
+        println!("{}",
+                 serde_json::to_string_pretty(&code::get_rules_schema()).unwrap());
+        return;
+    }
+
+    let apps: Vec<&str> = match matches.values_of("apps") {
+        Some(apps) => apps.collect(),
+        None => vec![matches.value_of("package").unwrap_or("")],
+    };
+    if matches.is_present("no_color") {
+        colored::control::set_override(false);
+    }
     let verbose = matches.is_present("verbose");
     let quiet = matches.is_present("quiet");
     let force = matches.is_present("force");
     let bench = matches.is_present("bench");
-    let config = match Config::new(app_id, verbose, quiet, force, bench) {
+    let mut config = match Config::new(apps[0], verbose, quiet, force, bench) {
         Ok(c) => c,
         Err(e) => {
             print_warning(format!("There was an error when reading the config.toml file: {}",
                                   e),
                           verbose);
             let mut c: Config = Default::default();
-            c.set_app_id(app_id);
+            c.set_app_id(apps[0]);
             c.set_verbose(verbose);
             c.set_quiet(quiet);
             c.set_force(force);
@@ -64,20 +82,18 @@ fn main() {
             c
         }
     };
-
-    if !config.check() {
-        let mut error_string = String::from("Configuration errors were found:\n");
-        for error in config.get_errors() {
-            error_string.push_str(&error);
-            error_string.push('\n');
-        }
-        error_string.push_str("The configuration was loaded, in order, from the following \
-                               files:\n\t- Default built-in configuration\n");
-        for file in config.get_loaded_config_files() {
-            error_string.push_str(&format!("\t- {}\n", file));
-        }
-        print_error(error_string, verbose);
-        exit(Error::Config.into());
+    let source_dir = matches.value_of("source_dir");
+    if let Err(e) = config.load_project_files(source_dir) {
+        print_warning(format!("There was an error when reading the {} file: {}",
+                              config::PROJECT_FILE,
+                              e),
+                      verbose);
+    }
+    set_cli_options(&mut config, &matches);
+    // The report written to the standard output cannot be mixed with the progress messages
+    if config.is_report_to_stdout() {
+        config.set_verbose(false);
+        config.set_quiet(true);
     }
 
     if config.is_verbose() {
@@ -90,67 +106,306 @@ fn main() {
                   application.");
         println!("You activated the verbose mode. {}",
                  "May Tux be with you!".bold());
+        for file in config.get_loaded_config_files() {
+            println!("Configuration loaded from {}.", file.italic());
+        }
         println!("");
         sleep(Duration::from_millis(1250));
     }
 
+    if let Some(id) = matches.value_of("explain") {
+        match explain_rule(&config, id) {
+            Ok(explanation) => {
+                print!("{}", explanation);
+                return;
+            }
+            Err(e) => {
+                print_error(format!("The rule {} could not be explained: {}", id, e), verbose);
+                exit(e.into());
+            }
+        }
+    }
+
+    if let Some(path) = matches.value_of("test_rules") {
+        match test_rules(&config, path) {
+            Ok(results) => {
+                for failure in results.get_failures() {
+                    println!("{} {}", "FAIL".red().bold(), failure);
+                }
+                let failed = results.get_failures().len();
+                println!("{} samples passed, {} failed.", results.get_passed(), failed);
+                if failed > 0 {
+                    exit(Error::RuleTestsFailed { failed: failed }.into());
+                }
+                return;
+            }
+            Err(e) => {
+                print_error(format!("The rules could not be tested: {}", e), verbose);
+                exit(e.into());
+            }
+        }
+    }
+
+    if let Some(file) = matches.value_of("file") {
+        match analyze_single_file_cli(&config, file) {
+            Ok(criticity) => {
+                if let Some(code) = criticity.and_then(|c| config.get_exit_code(c)) {
+                    exit(code);
+                }
+                return;
+            }
+            Err(e) => {
+                print_error(format!("The file {} could not be analyzed: {}", file, e), verbose);
+                exit(e.into());
+            }
+        }
+    }
+
+    // The rules are loaded once, and shared by the analyses of all the applications
+    let mut rules = None;
+    let mut exit_code = None;
+    let mut highest_criticity = None;
+    for app_id in &apps {
+        config.set_app_id(app_id);
+        if let Some(folder) = matches.value_of("source_dir") {
+            if matches.is_present("apps") {
+                config.set_source_folder(&format!("{}/{}", folder, app_id));
+            } else {
+                config.set_source_folder(folder);
+            }
+        }
+
+        if !config.check() {
+            let mut error_string = String::from("Configuration errors were found:\n");
+            for error in config.get_errors() {
+                error_string.push_str(&error);
+                error_string.push('\n');
+            }
+            error_string.push_str("The configuration was loaded, in order, from the following \
+                                   files:\n\t- Default built-in configuration\n");
+            for file in config.get_loaded_config_files() {
+                error_string.push_str(&format!("\t- {}\n", file));
+            }
+            print_error(error_string, verbose);
+            if apps.len() == 1 {
+                exit(Error::Config.into());
+            }
+            exit_code = Some(Error::Config.into());
+            continue;
+        }
+
+        if matches.is_present("watch") {
+            let mut paths = vec![config.get_analysis_folder()];
+            paths.extend(config.get_rules_files()
+                .into_iter()
+                .filter(|f| *f != "-")
+                .map(String::from));
+            watch(&config, &mut PollingWatcher::new(&paths));
+            return;
+        }
+
+        if rules.is_none() {
+            let rules_start = Instant::now();
+            match load_code_rules(&config) {
+                Ok(r) => rules = Some((Arc::new(r), rules_start.elapsed())),
+                Err(e) => exit(e.into()),
+            }
+        }
+        let (ref rules, rules_duration) = *rules.as_ref().unwrap();
+
+        if apps.len() > 1 && !config.is_quiet() {
+            println!("");
+            println!("{}", format!("Analyzing {}", app_id).bold());
+        }
+        match analyze_app(&config, rules.clone(), rules_duration) {
+            Ok(criticity) => highest_criticity = cmp::max(highest_criticity, criticity),
+            Err(e) => {
+                if apps.len() == 1 {
+                    exit(e.into());
+                }
+                print_error(format!("The analysis of {} failed: {}", app_id, e), verbose);
+                exit_code = Some(e.into());
+            }
+        }
+    }
+
+    // The errors of the analyzer take precedence over the exit codes of the findings
+    if let Some(code) = exit_code.or(highest_criticity.and_then(|c| config.get_exit_code(c))) {
+        exit(code);
+    }
+}
+
+/// Decompiles, analyzes and generates the report of the application in the configuration.
+///
+/// The rules must have been loaded beforehand with `load_code_rules()`, and the time it took to
+/// load them will be added to the benchmarks of the application. Returns the highest criticity of
+/// the reported findings, if there is any.
+fn analyze_app(config: &Config,
+               rules: Arc<Vec<Rule>>,
+               rules_duration: Duration)
+               -> Result<Option<Criticity>> {
     let mut benchmarks = if config.is_bench() {
-        Vec::with_capacity(4)
+        let mut benchmarks = Vec::with_capacity(5);
+        benchmarks.push(Benchmark::new("Rule loading", rules_duration));
+        benchmarks
     } else {
         Vec::with_capacity(0)
     };
 
     let start_time = Instant::now();
 
-    // APKTool app decompression
-    decompress(&config);
+    // An already decompiled source folder is analyzed directly
+    if config.get_source_folder().is_none() {
+        // APKTool app decompression
+        decompress(config);
 
-    if config.is_bench() {
-        benchmarks.push(Benchmark::new("ApkTool decompression", start_time.elapsed()));
-    }
+        if config.is_bench() {
+            benchmarks.push(Benchmark::new("ApkTool decompression", start_time.elapsed()));
+        }
 
-    // Extracting the classes.dex from the .apk file
-    extract_dex(&config, &mut benchmarks);
+        // Extracting the classes.dex from the .apk file
+        extract_dex(config, &mut benchmarks);
 
-    if config.is_verbose() {
-        println!("");
-        println!("Now it's time for the actual decompilation of the source code. We'll translate \
-                  Android JVM bytecode to Java, so that we can check the code afterwards.");
-    }
+        if config.is_verbose() {
+            println!("");
+            println!("Now it's time for the actual decompilation of the source code. We'll \
+                      translate Android JVM bytecode to Java, so that we can check the code \
+                      afterwards.");
+        }
 
-    let decompile_start = Instant::now();
+        let decompile_start = Instant::now();
 
-    // Decompiling the app
-    decompile(&config);
+        // Decompiling the app
+        decompile(config);
 
-    if config.is_bench() {
-        benchmarks.push(Benchmark::new("Decompilation", decompile_start.elapsed()));
+        if config.is_bench() {
+            benchmarks.push(Benchmark::new("Decompilation", decompile_start.elapsed()));
+        }
     }
 
-    if let Some(mut results) = Results::init(&config) {
+    if let Some(mut results) = Results::init(config) {
         if config.is_bench() {
             while benchmarks.len() > 0 {
                 results.add_benchmark(benchmarks.remove(0));
             }
         }
 
+        let lang = config.get_lang();
         let static_start = Instant::now();
         // Static application analysis
-        static_analysis(&config, &mut results);
+        try!(static_analysis(config, rules, &mut results));
 
         if config.is_bench() {
             results.add_benchmark(Benchmark::new("Total static analysis", static_start.elapsed()));
         }
 
+        if let Some(path) = config.get_baseline_generate() {
+            match fs::File::create(path)
+                .map_err(Error::from)
+                .and_then(|mut f| results.write_baseline(&mut f)) {
+                Ok(()) => {
+                    if !config.is_quiet() {
+                        println!("{}",
+                                 lang.format("baseline_written",
+                                             &[("count", &results.get_total_findings()),
+                                               ("path", &path)]));
+                    }
+                }
+                Err(e) => {
+                    print_error(format!("There was an error writing the baseline {}: {}",
+                                        path,
+                                        e),
+                                config.is_verbose());
+                    return Err(e);
+                }
+            }
+        }
+
+        if let Some(baseline) = config.get_baseline() {
+            match fs::File::open(baseline)
+                .map_err(Error::from)
+                .and_then(|f| results.suppress_baseline(f)) {
+                Ok(suppressed) => {
+                    if !config.is_quiet() {
+                        println!("{}",
+                                 lang.format("baseline_suppressed",
+                                             &[("count", &suppressed), ("path", &baseline)]));
+                    }
+                }
+                Err(e) => {
+                    print_error(format!("There was an error loading the baseline {}: {}",
+                                        baseline,
+                                        e),
+                                config.is_verbose());
+                    return Err(e);
+                }
+            }
+        }
+
+        if let Some(report) = config.get_compare_report() {
+            let comparison = match fs::File::open(report)
+                .map_err(Error::from)
+                .and_then(|f| results.compare(f)) {
+                Ok(c) => c,
+                Err(e) => {
+                    print_error(format!("There was an error comparing the findings with the \
+                                         report {}: {}",
+                                        report,
+                                        e),
+                                config.is_verbose());
+                    return Err(e);
+                }
+            };
+            if !config.is_quiet() {
+                println!("");
+                println!("{} {}",
+                         lang.format("compared_with", &[("path", &report)]).bold(),
+                         lang.format("comparison_summary",
+                                     &[("new", &comparison.get_new().len()),
+                                       ("existing", &comparison.get_existing().len()),
+                                       ("fixed", &comparison.get_fixed().len())]));
+            }
+            if config.is_only_new() {
+                results.remove_existing(&comparison);
+            }
+        }
+
+        if let Some(selector) = config.get_selector() {
+            let removed = results.select(selector);
+            if !config.is_quiet() {
+                println!("{}", lang.format("selection_summary", &[("count", &removed)]));
+            }
+        }
+
+        if let Some(max_findings) = config.get_max_findings() {
+            // The maximum is applied last, so that it only counts the findings to report.
+            results.truncate_findings(max_findings);
+        }
+
         // TODO dynamic analysis
 
         if !config.is_quiet() {
             println!("");
+            println!("{} {:.2}",
+                     format!("{}:", lang.get("application_score")).bold(),
+                     results.get_score());
+            println!("");
+            print!("{}",
+                   results.get_criticity_summary(colored::control::SHOULD_COLORIZE
+                       .should_colorize()));
+            if results.get_truncated_findings() > 0 {
+                println!("{}",
+                         lang.format("truncated_summary",
+                                     &[("count", &results.get_truncated_findings()),
+                                       ("max", &config.get_max_findings().unwrap())])
+                             .bold());
+            }
+            println!("");
         }
 
         let report_start = Instant::now();
 
-        match results.generate_report(&config) {
+        match results.generate_report(config) {
             Ok(_) => {
                 if config.is_verbose() {
                     println!("The results report has been saved. Everything went smoothly, now \
@@ -167,7 +422,7 @@ fn main() {
             Err(e) => {
                 print_error(format!("There was an error generating the results report: {}", e),
                             config.is_verbose());
-                exit(Error::Unknown.into())
+                return Err(Error::Unknown);
             }
         }
 
@@ -175,7 +430,34 @@ fn main() {
             results.add_benchmark(Benchmark::new("Report generation", report_start.elapsed()));
         }
 
-        if config.is_bench() {
+        if let Some(hook) = config.get_post_hook() {
+            let report = format!("{}/{}/results.json",
+                                 config.get_results_folder(),
+                                 config.get_app_id());
+            if config.is_verbose() {
+                println!("Running the post-analysis hook {}.", hook.join(" ").italic());
+            }
+            // The report was already generated, so failures are only reported as warnings
+            match run_post_hook(hook, &report, config) {
+                Ok(ref s) if s.success() => {}
+                Ok(s) => {
+                    print_warning(format!("The post-analysis hook {} failed: {}",
+                                          hook.join(" "),
+                                          s),
+                                  config.is_verbose())
+                }
+                Err(e) => {
+                    print_warning(format!("The post-analysis hook {} could not be run: {}",
+                                          hook.join(" "),
+                                          e),
+                                  config.is_verbose())
+                }
+            }
+        }
+
+        // The report generation and total times are only shown here, since the reports have
+        // already been written.
+        if config.is_bench() && !config.is_report_to_stdout() {
             results.add_benchmark(Benchmark::new("Total time", start_time.elapsed()));
             println!("");
             println!("{}", "Benchmarks:".bold());
@@ -183,8 +465,13 @@ fn main() {
                 println!("{}", bench);
             }
         }
-    } else if !config.is_quiet() {
-        println!("Analysis cancelled.");
+
+        Ok(results.get_highest_criticity())
+    } else {
+        if !config.is_quiet() {
+            println!("Analysis cancelled.");
+        }
+        Ok(None)
     }
 }
 
@@ -192,6 +479,61 @@ fn main() {
 pub enum Error {
     AppNotExists,
     ParseError,
+    /// A rule, or the list of rules if `rule` is `None`, does not have the expected structure.
+    ///
+    /// In this and the following rule errors, `rule` is the index of the rule in its file, `file`
+    /// is the rule file, if known, and `id` is the ID of the rule, or its label, if it has one.
+    InvalidRuleShape {
+        rule: Option<usize>,
+        field: Option<&'static str>,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// A regular expression of a rule could not be compiled.
+    RegexCompile {
+        rule: usize,
+        field: &'static str,
+        error: String,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// A rule requires a permission that is not known.
+    UnknownPermission {
+        rule: usize,
+        permission: String,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// The criticity of a rule is not valid.
+    InvalidCriticity {
+        rule: usize,
+        criticity: String,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// The forward check of a rule does not match the capture groups of its regular expression.
+    ForwardCheckMismatch {
+        rule: usize,
+        capture: &'static str,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// A regular expression of a rule matches the empty string.
+    EmptyMatch {
+        rule: usize,
+        field: &'static str,
+        file: Option<String>,
+        id: Option<String>,
+    },
+    /// Two rules have the same ID, in the same file or in different ones.
+    DuplicateRuleId { id: String },
+    FileTimeout,
+    /// The folder with the source code of the application does not exist.
+    SourceNotFound { path: String },
+    /// There are no source files to analyze in the folder of the application.
+    NoSourceFiles { path: String },
+    /// Some samples of the rule test vectors did not give the expected result.
+    RuleTestsFailed { failed: usize },
     JSONError(JSONError),
     CodeNotFound,
     Config,
@@ -199,14 +541,72 @@ pub enum Error {
     Unknown,
 }
 
+impl Error {
+    /// Sets the rule file of the errors of invalid rules. Other errors are returned unchanged.
+    pub fn in_rule_file<S: Into<String>>(mut self, rule_file: S) -> Error {
+        match self {
+            Error::InvalidRuleShape { ref mut file, .. } |
+            Error::RegexCompile { ref mut file, .. } |
+            Error::UnknownPermission { ref mut file, .. } |
+            Error::InvalidCriticity { ref mut file, .. } |
+            Error::ForwardCheckMismatch { ref mut file, .. } |
+            Error::EmptyMatch { ref mut file, .. } => *file = Some(rule_file.into()),
+            _ => {}
+        }
+        self
+    }
+
+    /// Sets the ID of the rule of the errors of invalid rules, getting it from the index of the
+    /// rule in its file. Other errors are returned unchanged.
+    pub fn with_rule_id<F>(mut self, get_id: F) -> Error
+        where F: FnOnce(usize) -> Option<String>
+    {
+        match self {
+            Error::InvalidRuleShape { rule: Some(rule), ref mut id, .. } |
+            Error::RegexCompile { rule, ref mut id, .. } |
+            Error::UnknownPermission { rule, ref mut id, .. } |
+            Error::InvalidCriticity { rule, ref mut id, .. } |
+            Error::ForwardCheckMismatch { rule, ref mut id, .. } |
+            Error::EmptyMatch { rule, ref mut id, .. } => *id = get_id(rule),
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Describes the rule in the given index of a rule file for the rule errors.
+fn describe_rule(rule: usize, file: &Option<String>, id: &Option<String>) -> String {
+    let mut description = match *id {
+        Some(ref id) => format!("the rule {} at index {}", id, rule),
+        None => format!("the rule at index {}", rule),
+    };
+    match *file {
+        Some(ref file) if file == "-" => description.push_str(" of the standard input"),
+        Some(ref file) => description.push_str(&format!(" of the {} rule file", file)),
+        None => {}
+    }
+    description
+}
+
 impl Into<i32> for Error {
     fn into(self) -> i32 {
         match self {
             Error::AppNotExists => 10,
-            Error::ParseError => 20,
+            Error::ParseError |
+            Error::InvalidRuleShape { .. } |
+            Error::RegexCompile { .. } |
+            Error::UnknownPermission { .. } |
+            Error::InvalidCriticity { .. } |
+            Error::ForwardCheckMismatch { .. } |
+            Error::EmptyMatch { .. } |
+            Error::DuplicateRuleId { .. } => 20,
             Error::JSONError(_) => 30,
             Error::CodeNotFound => 40,
             Error::Config => 50,
+            Error::FileTimeout => 60,
+            Error::SourceNotFound { .. } => 70,
+            Error::NoSourceFiles { .. } => 80,
+            Error::RuleTestsFailed { .. } => 90,
             Error::IOError(_) => 100,
             Error::Unknown => 1,
         }
@@ -233,7 +633,84 @@ impl From<serde_json::error::Error> for Error {
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        match *self {
+            Error::InvalidRuleShape { rule: None, ref file, .. } => {
+                match *file {
+                    Some(ref file) if file == "-" => {
+                        write!(f, "the rules of the standard input must be a JSON array")
+                    }
+                    Some(ref file) => {
+                        write!(f, "the rules of the {} rule file must be a JSON array", file)
+                    }
+                    None => write!(f, "rules must be a JSON array"),
+                }
+            }
+            Error::InvalidRuleShape { rule: Some(rule), field: None, ref file, ref id } => {
+                write!(f,
+                       "{} does not have the expected structure",
+                       describe_rule(rule, file, id))
+            }
+            Error::InvalidRuleShape { rule: Some(rule), field: Some(field), ref file, ref id } => {
+                write!(f,
+                       "the {} attribute of {} is not valid",
+                       field,
+                       describe_rule(rule, file, id))
+            }
+            Error::RegexCompile { rule, field, ref error, ref file, ref id } => {
+                write!(f,
+                       "an error occurred when compiling the regular expresion in the {} \
+                        attribute of {}: {}",
+                       field,
+                       describe_rule(rule, file, id),
+                       error)
+            }
+            Error::UnknownPermission { rule, ref permission, ref file, ref id } => {
+                write!(f,
+                       "the permission {} of {} is unknown",
+                       permission,
+                       describe_rule(rule, file, id))
+            }
+            Error::InvalidCriticity { rule, ref criticity, ref file, ref id } => {
+                write!(f,
+                       "the criticity {} of {} must be one of warning, low, medium, high or \
+                        critical",
+                       criticity,
+                       describe_rule(rule, file, id))
+            }
+            Error::ForwardCheckMismatch { rule, capture, ref file, ref id } => {
+                write!(f,
+                       "the forward check of {} does not match the {} capture group",
+                       describe_rule(rule, file, id),
+                       capture)
+            }
+            Error::EmptyMatch { rule, field, ref file, ref id } => {
+                write!(f,
+                       "the regular expression in the {} attribute of {} matches the empty \
+                        string",
+                       field,
+                       describe_rule(rule, file, id))
+            }
+            Error::DuplicateRuleId { ref id } => {
+                write!(f, "the rule ID {} is used by more than one rule", id)
+            }
+            Error::NoSourceFiles { ref path } => {
+                write!(f,
+                       "there are no source files to analyze in the {} folder. Check the path, \
+                        or the extensions given with --ext",
+                       path)
+            }
+            Error::RuleTestsFailed { failed } => {
+                write!(f, "{} samples of the rule test vectors failed", failed)
+            }
+            Error::SourceNotFound { ref path } => {
+                write!(f,
+                       "the source code folder {} does not exist. Check that the application \
+                        was decompiled correctly, or run the analysis again with --force",
+                       path)
+            }
+            Error::IOError(ref e) => write!(f, "{}", e),
+            _ => write!(f, "{}", self.description()),
+        }
     }
 }
 
@@ -242,9 +719,22 @@ impl StdError for Error {
         match *self {
             Error::AppNotExists => "the application has not been found",
             Error::ParseError => "there was an error in some parsing process",
+            Error::InvalidRuleShape { .. } => "a rule does not have the expected structure",
+            Error::RegexCompile { .. } => "a regular expression of a rule could not be compiled",
+            Error::UnknownPermission { .. } => "a rule requires an unknown permission",
+            Error::InvalidCriticity { .. } => "the criticity of a rule is not valid",
+            Error::ForwardCheckMismatch { .. } => {
+                "the forward check of a rule does not match its capture groups"
+            }
+            Error::EmptyMatch { .. } => "a regular expression of a rule matches the empty string",
+            Error::DuplicateRuleId { .. } => "two rules have the same ID",
             Error::JSONError(ref e) => e.description(),
             Error::CodeNotFound => "the code was not found in the file",
             Error::Config => "there was an error in the configuration",
+            Error::FileTimeout => "the analysis of the file took too long",
+            Error::SourceNotFound { .. } => "the source code folder does not exist",
+            Error::NoSourceFiles { .. } => "there are no source files to analyze",
+            Error::RuleTestsFailed { .. } => "some samples of the rule test vectors failed",
             Error::IOError(ref e) => e.description(),
             Error::Unknown => "an unknown error occurred",
         }
@@ -321,7 +811,46 @@ impl FromStr for Criticity {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl Serialize for Confidence {
+    fn serialize<S>(&self, serializer: &mut S) -> result::Result<(), S::Error>
+        where S: Serializer
+    {
+        try!(serializer.serialize_str(format!("{}", self).as_str()));
+        Ok(())
+    }
+}
+
+impl FromStr for Confidence {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Confidence> {
+        match s.to_lowercase().as_str() {
+            "high" => Ok(Confidence::High),
+            "medium" => Ok(Confidence::Medium),
+            "low" => Ok(Confidence::Low),
+            _ => Err(Error::ParseError),
+        }
+    }
+}
+
 fn get_help_menu() -> ArgMatches<'static> {
+    get_cli_app().get_matches()
+}
+
+/// Gets the definition of the command line interface.
+fn get_cli_app() -> App<'static, 'static> {
     App::new("SUPER Android Analyzer")
         .version(crate_version!())
         .author("SUPER Team <contact@superanalyzer.rocks>")
@@ -329,25 +858,573 @@ fn get_help_menu() -> ArgMatches<'static> {
         .arg(Arg::with_name("package")
             .help("The package string of the application to test.")
             .value_name("package")
-            .required(true)
+            .required_unless_one(&["rules_schema", "apps", "file", "explain", "test_rules"])
+            .conflicts_with_all(&["apps", "file"])
             .takes_value(true))
         .arg(Arg::with_name("verbose")
             .short("v")
             .long("verbose")
             .conflicts_with("quiet")
             .help("If you'd like the auditor to talk more than necessary."))
+        .arg(Arg::with_name("no_color")
+            .long("no-color")
+            .help("Do not use colors in the output. They are not used either if the output is \
+                   not a terminal."))
         .arg(Arg::with_name("force")
             .long("force")
             .help("If you'd like to force the auditor to do everything from the beginning."))
         .arg(Arg::with_name("bench")
             .long("bench")
-            .help("Show benchmarks for the analysis."))
+            .help("Show benchmarks for the analysis. They are also added to the JSON, SARIF and \
+                   CSV reports, except for the report generation and total times."))
         .arg(Arg::with_name("quiet")
             .short("q")
             .long("quiet")
             .conflicts_with("verbose")
             .help("If you'd like a zen auditor that won't talk unless it's 100% necessary."))
-        .get_matches()
+        .arg(Arg::with_name("debug")
+            .long("debug")
+            .help("Show debug messages, such as the reasons why each code rule was skipped."))
+        .arg(Arg::with_name("min_confidence")
+            .long("min-confidence")
+            .value_name("confidence")
+            .takes_value(true)
+            .possible_values(&["low", "medium", "high"])
+            .help("Only run the code rules with at least this confidence."))
+        .arg(Arg::with_name("output_json_gz")
+            .long("output-json-gz")
+            .value_name("path")
+            .takes_value(true)
+            .help("Also write the JSON report compressed with gzip to the given path."))
+        .arg(Arg::with_name("output_txt")
+            .long("output-txt")
+            .value_name("path")
+            .takes_value(true)
+            .help("Also write a text report to the given path, with a sorted line per finding \
+                   and nothing else, so that it can be compared with diff."))
+        .arg(Arg::with_name("output_by_file")
+            .long("output-by-file")
+            .value_name("path")
+            .takes_value(true)
+            .help("Also write a JSON report to the given path with the findings grouped by file, \
+                   for editor integrations."))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("format")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .possible_values(&["json", "html", "csv", "sarif", "txt"])
+            .help("Also write the report in the given format, to the standard output or to the \
+                   file given with --output. Can be used multiple times with --output-dir. When \
+                   the report is written to the standard output, nothing else is printed there, \
+                   as with --quiet."))
+        .arg(Arg::with_name("output")
+            .long("output")
+            .value_name("path")
+            .takes_value(true)
+            .requires("format")
+            .help("Write the report selected with --format to the given path."))
+        .arg(Arg::with_name("output_dir")
+            .long("output-dir")
+            .value_name("path")
+            .takes_value(true)
+            .conflicts_with("output")
+            .help("Write the report in each of the formats selected with --format, or in all of \
+                   them if none is selected, to the given folder, as report.json, report.sarif \
+                   and so on. The folder is created if needed."))
+        .arg(Arg::with_name("lang")
+            .long("lang")
+            .value_name("lang")
+            .takes_value(true)
+            .possible_values(&["en", "es"])
+            .help("Language of the texts of the reports, such as the section headers. The \
+                   findings are not translated."))
+        .arg(Arg::with_name("enable_category")
+            .long("enable-category")
+            .value_name("category")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Only run the code rules of the given category. The tapjacking and screenshots \
+                   categories only run if enabled. Can be used multiple times."))
+        .arg(Arg::with_name("disable_category")
+            .long("disable-category")
+            .value_name("category")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Do not run the code rules of the given category. Can be used multiple times."))
+        .arg(Arg::with_name("include_tag")
+            .long("include-tag")
+            .value_name("tag")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Also run the code rules with the given tag, even if it is excluded by \
+                   default, such as experimental. Can be used multiple times."))
+        .arg(Arg::with_name("exclude_tag")
+            .long("exclude-tag")
+            .value_name("tag")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Do not run the code rules with the given tag. The experimental tag is excluded \
+                   by default. Can be used multiple times."))
+        .arg(Arg::with_name("cache_dir")
+            .long("cache-dir")
+            .value_name("path")
+            .takes_value(true)
+            .help("Folder where the findings of each file are cached between analyses."))
+        .arg(Arg::with_name("no_cache")
+            .long("no-cache")
+            .conflicts_with("cache_dir")
+            .help("Analyze all the source files again, without using the cache."))
+        .arg(Arg::with_name("no_manifest")
+            .long("no-manifest")
+            .help("Skip the analysis of the manifest. The code is analyzed without it, so the \
+                   rules that require permissions never match."))
+        .arg(Arg::with_name("no_certificate")
+            .long("no-certificate")
+            .help("Skip the analysis of the certificates of the application."))
+        .arg(Arg::with_name("no_code")
+            .long("no-code")
+            .help("Skip the analysis of the source code with the code rules."))
+        .arg(Arg::with_name("rules")
+            .long("rules")
+            .value_name("path")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Load the code rules from the given file instead of the default one. Use - to \
+                   read them from the standard input. Can be used multiple times."))
+        .arg(Arg::with_name("rules_dir")
+            .long("rules-dir")
+            .value_name("path")
+            .takes_value(true)
+            .help("Load the code rules from every JSON file of the given directory, in \
+                   alphabetical order, instead of the default file. They are loaded after the \
+                   ones given with --rules."))
+        .arg(Arg::with_name("rules_dir_recursive")
+            .long("rules-dir-recursive")
+            .requires("rules_dir")
+            .help("Also load the rule files in the subdirectories of the rules directory."))
+        .arg(Arg::with_name("extension")
+            .long("ext")
+            .value_name("extension")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Also analyze the source files with the given extension, apart from the XML and \
+                   Java files. Can be used multiple times."))
+        .arg(Arg::with_name("skip_package")
+            .long("skip-package")
+            .value_name("package")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Do not analyze the source files of the given package, such as com/facebook. \
+                   The android and com/google/android/gms packages are always skipped. Can be \
+                   used multiple times."))
+        .arg(Arg::with_name("threads")
+            .long("threads")
+            .value_name("N")
+            .takes_value(true)
+            .validator(|s| match s.parse::<u8>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("the number of threads must be between 1 and 255")),
+            })
+            .help("Analyze the code with N threads, instead of the ones in config.toml."))
+        .arg(Arg::with_name("single_thread")
+            .long("single-thread")
+            .conflicts_with("threads")
+            .help("Analyze the code in a single thread, in the order of the file paths, so that \
+                   runs can be reproduced and debugged. The findings are the same."))
+        .arg(Arg::with_name("file_timeout")
+            .long("file-timeout")
+            .value_name("seconds")
+            .takes_value(true)
+            .validator(|s| match s.parse::<u64>() {
+                Ok(t) if t > 0 => Ok(()),
+                _ => Err(String::from("the timeout must be a positive number of seconds")),
+            })
+            .help("Stop analyzing a source file if it takes longer than the given seconds."))
+        .arg(Arg::with_name("mmap_threshold")
+            .long("mmap-threshold")
+            .value_name("bytes")
+            .takes_value(true)
+            .validator(|s| match s.parse::<u64>() {
+                Ok(_) => Ok(()),
+                _ => Err(String::from("the threshold must be a number of bytes")),
+            })
+            .help("Memory-map the source files larger than the given bytes instead of reading \
+                   them, to reduce the memory used by huge files."))
+        .arg(Arg::with_name("apps")
+            .long("apps")
+            .value_name("package")
+            .takes_value(true)
+            .multiple(true)
+            .conflicts_with_all(&["output_json_gz",
+                                  "output_txt",
+                                  "output_by_file",
+                                  "output",
+                                  "output_dir"])
+            .help("Analyze all the given packages, generating a report for each of them. With \
+                   --source-dir, the source of each package must be in a folder named after it."))
+        .arg(Arg::with_name("rules_schema")
+            .long("rules-schema")
+            .help("Print the JSON Schema of the rule files and exit."))
+        .arg(Arg::with_name("rule_overrides")
+            .long("rule-overrides")
+            .value_name("path")
+            .takes_value(true)
+            .help("Override the criticity and confidence of the rules with the given TOML file."))
+        .arg(Arg::with_name("known_good")
+            .long("known-good")
+            .value_name("path")
+            .takes_value(true)
+            .help("Skip the source files whose SHA-256 hash is in the given file, with a hash per \
+                   line, such as the output of sha256sum."))
+        .arg(Arg::with_name("strict_rules")
+            .long("strict-rules")
+            .help("Stop with an error instead of a warning if there is any problem loading the \
+                   code rules or their overrides."))
+        .arg(Arg::with_name("absolute_paths")
+            .long("absolute-paths")
+            .help("Report the files of the findings with absolute paths. The HTML report only \
+                   links to the code of the files with relative paths."))
+        .arg(Arg::with_name("require_files")
+            .long("require-files")
+            .help("Fail if there are no source files to analyze, instead of reporting no \
+                   vulnerabilities."))
+        .arg(Arg::with_name("follow_symlinks")
+            .long("follow-symlinks")
+            .help("Follow the symbolic links in the source folder, instead of skipping them. \
+                   Link cycles are still skipped."))
+        .arg(Arg::with_name("compare")
+            .long("compare")
+            .value_name("previous.json")
+            .takes_value(true)
+            .help("Compare the findings with the ones of the given previous JSON report, \
+                   printing how many are new, existing and fixed."))
+        .arg(Arg::with_name("only_new")
+            .long("only-new")
+            .requires("compare")
+            .help("Only report the findings that were not in the report given with --compare."))
+        .arg(Arg::with_name("baseline")
+            .long("baseline")
+            .value_name("path")
+            .takes_value(true)
+            .help("Suppress the findings of the given baseline, written with \
+                   --baseline-generate, so that only the new ones are reported."))
+        .arg(Arg::with_name("baseline_generate")
+            .long("baseline-generate")
+            .value_name("path")
+            .takes_value(true)
+            .conflicts_with("apps")
+            .help("Write all the findings of the analysis to the given baseline file, to \
+                   suppress them in later analyses with --baseline. --max-findings does not \
+                   limit them."))
+        .arg(Arg::with_name("max_findings")
+            .long("max-findings")
+            .value_name("N")
+            .takes_value(true)
+            .validator(|s| match s.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(()),
+                _ => Err(String::from("the maximum number of findings must be a positive number")),
+            })
+            .help("Report at most N findings, keeping the most critical ones. The limit is \
+                   applied after --baseline, --only-new and --select, and if none of them is \
+                   given, the analysis stops once N findings are found."))
+        .arg(Arg::with_name("select")
+            .long("select")
+            .value_name("expr")
+            .takes_value(true)
+            .validator(|s| Selector::new(&s).map(|_| ()))
+            .help("Only report the findings selected by the given expression, such as \
+                   'criticity>=high && file~\"payment\"'. The criticity, file, rule and label \
+                   fields can be compared."))
+        .arg(Arg::with_name("include_smali")
+            .long("include-smali")
+            .help("Also analyze the smali code of the application, so that the rules can find \
+                   patterns in the bytecode. It is skipped by default, since it is slower."))
+        .arg(Arg::with_name("include_generated")
+            .long("include-generated")
+            .help("Also analyze the code generated by the build tools: the BuildConfig.java \
+                   files and the files in databinding and generated folders."))
+        .arg(Arg::with_name("redact_secrets")
+            .long("redact-secrets")
+            .help("Mask the secrets found by the rules, such as keys, in the code of the \
+                   findings, keeping their first and last 2 characters."))
+        .arg(Arg::with_name("normalize_newlines")
+            .long("normalize-newlines")
+            .help("Convert the CRLF line endings of the source files to LF before analyzing \
+                   them, so that the rules that match the end of the lines work in files \
+                   written in Windows."))
+        .arg(Arg::with_name("exit_code")
+            .long("exit-code")
+            .value_name("criticity=code")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|s| parse_exit_code(&s).map(|_| ()))
+            .help("Exit with the given code if the highest criticity of the findings is the given \
+                   one, for example critical=2. Can be used multiple times. Errors of the \
+                   analyzer take precedence."))
+        .arg(Arg::with_name("source_dir")
+            .long("source-dir")
+            .value_name("path")
+            .takes_value(true)
+            .help("Analyze the given already decompiled source folder instead of decompiling \
+                   the APK of the package."))
+        .arg(Arg::with_name("post_hook")
+            .long("post-hook")
+            .value_name("command")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Run the given command once the report is generated, with the path of the \
+                   JSON report as its last argument and in the SUPER_REPORT environment \
+                   variable. The program and each of its arguments are given with a separate \
+                   --post-hook. It is run with the permissions of the analyzer, so only use \
+                   trusted commands."))
+        .arg(Arg::with_name("file")
+            .long("file")
+            .value_name("path")
+            .takes_value(true)
+            .conflicts_with_all(&["apps", "source_dir", "watch"])
+            .help("Only analyze the given source file with the code rules, printing a line per \
+                   finding, to test the rules quickly. No report is generated."))
+        .arg(Arg::with_name("explain")
+            .long("explain")
+            .value_name("rule_id")
+            .takes_value(true)
+            .conflicts_with_all(&["apps", "file", "watch"])
+            .help("Print all the details of the rule with the given ID, and exit without \
+                   analyzing anything."))
+        .arg(Arg::with_name("test_rules")
+            .long("test-rules")
+            .value_name("vectors.json")
+            .takes_value(true)
+            .conflicts_with_all(&["apps", "file", "explain", "watch"])
+            .help("Run the code rules against the samples of the given test vectors file, \
+                   printing the failed ones, and exit without analyzing anything."))
+        .arg(Arg::with_name("watch")
+            .long("watch")
+            .requires("source_dir")
+            .conflicts_with("apps")
+            .help("Analyze the code again each time the source folder or the rules change."))
+}
+
+/// Analyzes a single source file with the code rules, and prints its findings to the standard
+/// output, with the format of the text report. Returns the highest criticity of the findings, if
+/// there is any.
+fn analyze_single_file_cli(config: &Config, file: &str) -> Result<Option<Criticity>> {
+    let rules = Arc::new(try!(load_code_rules(config)));
+    let vulnerabilities = try!(code::analyze_single_file(file, rules, config));
+    let highest = vulnerabilities.iter().map(|v| v.get_criticity()).max();
+
+    let mut results: Results = Default::default();
+    for vuln in vulnerabilities {
+        results.add_vulnerability(vuln);
+    }
+    let stdout = io::stdout();
+    try!(results.write_report(ReportFormat::Txt, &mut stdout.lock()));
+    Ok(highest)
+}
+
+/// Sets the options given in the command line in the configuration, overriding the ones of the
+/// configuration files.
+fn set_cli_options(config: &mut Config, matches: &ArgMatches) {
+    if matches.is_present("debug") {
+        config.set_debug(true);
+    }
+    if let Some(confidence) = matches.value_of("min_confidence") {
+        config.set_min_confidence(Confidence::from_str(confidence).unwrap());
+    }
+    if let Some(path) = matches.value_of("output_json_gz") {
+        config.set_output_json_gz(path);
+    }
+    if let Some(path) = matches.value_of("output_txt") {
+        config.set_output_txt(path);
+    }
+    if let Some(path) = matches.value_of("output_by_file") {
+        config.set_output_by_file(path);
+    }
+    if let Some(command) = matches.values_of("post_hook") {
+        config.set_post_hook(command.map(String::from));
+    }
+    if let Some(formats) = matches.values_of("format") {
+        config.set_report_formats(formats.map(|f| ReportFormat::from_str(f).unwrap()));
+    }
+    if let Some(path) = matches.value_of("output") {
+        config.set_output(path);
+    }
+    if let Some(lang) = matches.value_of("lang") {
+        config.set_lang(Lang::from_str(lang).unwrap());
+    }
+    if let Some(categories) = matches.values_of("enable_category") {
+        for category in categories {
+            config.enable_category(category);
+        }
+    }
+    if let Some(categories) = matches.values_of("disable_category") {
+        for category in categories {
+            config.disable_category(category);
+        }
+    }
+    if let Some(tags) = matches.values_of("include_tag") {
+        for tag in tags {
+            config.include_tag(tag);
+        }
+    }
+    if let Some(tags) = matches.values_of("exclude_tag") {
+        for tag in tags {
+            config.exclude_tag(tag);
+        }
+    }
+    if let Some(folder) = matches.value_of("cache_dir") {
+        config.set_cache_folder(folder);
+    }
+    if matches.is_present("no_cache") {
+        config.set_cache_enabled(false);
+    }
+    if matches.is_present("no_manifest") {
+        config.set_manifest_analysis_enabled(false);
+    }
+    if matches.is_present("no_certificate") {
+        config.set_certificate_analysis_enabled(false);
+    }
+    if matches.is_present("no_code") {
+        config.set_code_analysis_enabled(false);
+    }
+    if let Some(files) = matches.values_of("rules") {
+        for file in files {
+            config.add_rules_file(file);
+        }
+    }
+    if let Some(folder) = matches.value_of("rules_dir") {
+        match get_rules_dir_files(folder, matches.is_present("rules_dir_recursive")) {
+            Ok(files) => {
+                for file in files {
+                    config.add_rules_file(&file);
+                }
+            }
+            Err(e) => {
+                print_error(format!("There was an error reading the rules directory {}: {}",
+                                    folder,
+                                    e),
+                            config.is_verbose());
+                exit(Error::Config.into());
+            }
+        }
+    }
+    if let Some(extensions) = matches.values_of("extension") {
+        for extension in extensions {
+            config.add_extension(extension);
+        }
+    }
+    if let Some(packages) = matches.values_of("skip_package") {
+        for package in packages {
+            config.skip_package(package);
+        }
+    }
+    if let Some(threads) = matches.value_of("threads") {
+        config.set_threads(threads.parse().unwrap());
+    }
+    if matches.is_present("single_thread") {
+        config.set_single_thread(true);
+    }
+    if let Some(timeout) = matches.value_of("file_timeout") {
+        config.set_file_timeout(Duration::from_secs(timeout.parse().unwrap()));
+    }
+    if let Some(threshold) = matches.value_of("mmap_threshold") {
+        config.set_mmap_threshold(threshold.parse().unwrap());
+    }
+    if let Some(file) = matches.value_of("rule_overrides") {
+        config.set_rule_overrides(file);
+    }
+    if let Some(file) = matches.value_of("known_good") {
+        config.set_known_good(file);
+    }
+    if matches.is_present("strict_rules") {
+        config.set_strict_rules(true);
+    }
+    if matches.is_present("absolute_paths") {
+        config.set_absolute_paths(true);
+    }
+    if matches.is_present("require_files") {
+        config.set_require_files(true);
+    }
+    if matches.is_present("follow_symlinks") {
+        config.set_follow_symlinks(true);
+    }
+    if let Some(report) = matches.value_of("compare") {
+        config.set_compare_report(report);
+    }
+    if matches.is_present("only_new") {
+        config.set_only_new(true);
+    }
+    if let Some(path) = matches.value_of("baseline") {
+        config.set_baseline(path);
+    }
+    if let Some(path) = matches.value_of("baseline_generate") {
+        config.set_baseline_generate(path);
+    }
+    if matches.is_present("include_smali") {
+        config.set_include_smali(true);
+    }
+    if matches.is_present("include_generated") {
+        config.set_include_generated(true);
+    }
+    if matches.is_present("redact_secrets") {
+        config.set_redact_secrets(true);
+    }
+    if matches.is_present("normalize_newlines") {
+        config.set_normalize_newlines(true);
+    }
+    if let Some(max_findings) = matches.value_of("max_findings") {
+        config.set_max_findings(max_findings.parse().unwrap());
+    }
+    if let Some(expr) = matches.value_of("select") {
+        config.set_selector(Selector::new(expr).unwrap());
+    }
+    if let Some(exit_codes) = matches.values_of("exit_code") {
+        for exit_code in exit_codes {
+            let (criticity, code) = parse_exit_code(exit_code).unwrap();
+            config.set_exit_code(criticity, code);
+        }
+    }
+}
+
+/// Parses an exit code for a criticity, in the `criticity=code` format of the command line.
+fn parse_exit_code(exit_code: &str) -> result::Result<(Criticity, i32), String> {
+    let mut parts = exit_code.splitn(2, '=');
+    let criticity = match Criticity::from_str(parts.next().unwrap().trim()) {
+        Ok(c) => c,
+        Err(_) => {
+            return Err(String::from("the criticity must be one of warning, low, medium, high or \
+                                     critical"))
+        }
+    };
+    match parts.next().map(|c| c.trim().parse::<u8>()) {
+        Some(Ok(code)) => Ok((criticity, code as i32)),
+        _ => Err(String::from("the exit code must be a number between 0 and 255")),
+    }
+}
+
+/// Runs the command given to be run once the report is generated, returning its exit status.
+///
+/// The command is the program followed by its arguments, and it is run directly, not through a
+/// shell, with the path of the JSON report as its last argument. The path is also given in the
+/// `SUPER_REPORT` environment variable, and the application ID in `SUPER_APP_ID`.
+fn run_post_hook(hook: &[String], report: &str, config: &Config) -> io::Result<ExitStatus> {
+    Command::new(&hook[0])
+        .args(&hook[1..])
+        .arg(report)
+        .env("SUPER_REPORT", report)
+        .env("SUPER_APP_ID", config.get_app_id())
+        .status()
 }
 
 /// Copies the contents of `from` to `to`
@@ -373,8 +1450,13 @@ pub fn copy_folder<P: AsRef<Path>>(from: P, to: P) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use Criticity;
+    use {Criticity, Confidence, Config};
+    use std::fs;
+    use std::path::Path;
     use std::str::FromStr;
+    use std::fs::File;
+    use std::io::Write;
+    use super::{parse_exit_code, run_post_hook, get_cli_app, set_cli_options};
 
     #[test]
     fn it_criticity() {
@@ -424,4 +1506,118 @@ mod tests {
         assert_eq!(format!("{:?}", Criticity::High).as_str(), "High");
         assert_eq!(format!("{:?}", Criticity::Critical).as_str(), "Critical");
     }
+
+    #[test]
+    fn it_confidence() {
+        assert_eq!(Confidence::from_str("low").unwrap(), Confidence::Low);
+        assert_eq!(Confidence::from_str("Medium").unwrap(), Confidence::Medium);
+        assert_eq!(Confidence::from_str("HIGH").unwrap(), Confidence::High);
+        assert!(Confidence::from_str("critical").is_err());
+
+        assert!(Confidence::Low < Confidence::Medium);
+        assert!(Confidence::Medium < Confidence::High);
+
+        assert_eq!(format!("{}", Confidence::Low).as_str(), "low");
+        assert_eq!(format!("{}", Confidence::Medium).as_str(), "medium");
+        assert_eq!(format!("{}", Confidence::High).as_str(), "high");
+    }
+
+    #[test]
+    fn it_parse_exit_code() {
+        assert_eq!(parse_exit_code("critical=2").unwrap(), (Criticity::Critical, 2));
+        assert_eq!(parse_exit_code("High = 3").unwrap(), (Criticity::High, 3));
+        assert!(parse_exit_code("severe=2").is_err());
+        assert!(parse_exit_code("high").is_err());
+        assert!(parse_exit_code("high=256").is_err());
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn it_post_hook() {
+        let folder = "test_post_hook";
+        fs::create_dir_all(folder).unwrap();
+        let report = format!("{}/results.json", folder);
+        let marker = format!("{}/marker", folder);
+        let config: Config = Default::default();
+
+        let hook = vec![String::from("touch"), marker.clone()];
+        assert!(run_post_hook(&hook, &report, &config).unwrap().success());
+        assert!(Path::new(&marker).exists());
+        // The report path is given as the last argument
+        assert!(Path::new(&report).exists());
+
+        // The arguments are given as they are, without splitting them
+        let matches = get_cli_app().get_matches_from(vec!["super",
+                                                          "--post-hook",
+                                                          "touch",
+                                                          "--post-hook",
+                                                          "test_post_hook/with space",
+                                                          "com.example"]);
+        let mut config: Config = Default::default();
+        set_cli_options(&mut config, &matches);
+        let hook = config.get_post_hook().unwrap();
+        assert_eq!(hook.len(), 2);
+        assert!(run_post_hook(hook, &report, &config).unwrap().success());
+        assert!(Path::new("test_post_hook/with space").exists());
+
+        let hook = vec![String::from("false")];
+        assert!(!run_post_hook(&hook, &report, &config).unwrap().success());
+        let hook = vec![String::from("test_post_hook_missing_command")];
+        assert!(run_post_hook(&hook, &report, &config).is_err());
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn it_project_file() {
+        let folder = "test_project_file";
+        fs::create_dir_all(folder).unwrap();
+        {
+            let mut f = File::create(format!("{}/super.toml", folder)).unwrap();
+            f.write_all(b"threads = 4\nmin_confidence = \"high\"\ninclude_smali = true\n\
+                          max_findings = 0\nrules = [\"rules.json\", \"-\"]\n\
+                          output = \"/tmp/report.json\"\nbaseline = \"baseline.json\"\n")
+                .unwrap();
+        }
+        let mut config: Config = Default::default();
+        config.load_project_file(format!("{}/super.toml", folder)).unwrap();
+        assert_eq!(config.get_threads(), 4);
+        assert_eq!(config.get_min_confidence(), Confidence::High);
+        assert!(config.is_include_smali());
+        // Invalid values are ignored
+        assert_eq!(config.get_max_findings(), None);
+        // Relative paths are resolved against the folder of the file
+        assert_eq!(config.get_rules_files(), vec!["test_project_file/rules.json", "-"]);
+        assert_eq!(config.get_output(), Some("/tmp/report.json"));
+        assert_eq!(config.get_baseline(), Some("test_project_file/baseline.json"));
+
+        // The file of the analyzed application cannot run commands nor hide findings
+        {
+            let mut f = File::create(format!("{}/super.toml", folder)).unwrap();
+            f.write_all(b"threads = 4\nmin_confidence = \"high\"\ninclude_smali = true\n\
+                          post_hook = \"rm -rf /\"\ncode_analysis = false\n\
+                          disable_categories = [\"crypto\"]\nselect = \"criticity == low\"\n\
+                          output = \"report.json\"\n")
+                .unwrap();
+        }
+        let mut config: Config = Default::default();
+        config.load_project_files(Some(folder)).unwrap();
+        assert_eq!(config.get_threads(), 4);
+        assert!(config.is_include_smali());
+        assert_eq!(config.get_min_confidence(), Confidence::Low);
+        assert_eq!(config.get_post_hook(), None);
+        assert!(config.is_code_analysis_enabled());
+        assert_eq!(config.get_disabled_categories().count(), 0);
+        assert!(config.get_selector().is_none());
+        assert_eq!(config.get_output(), None);
+        assert_eq!(config.get_loaded_config_files().last().map(|f| f.as_str()),
+                   Some("test_project_file/super.toml"));
+
+        // The command line options override the ones of the file
+        let args = vec!["super", "--threads", "8", "--source-dir", folder, "com.example"];
+        let matches = get_cli_app().get_matches_from(args);
+        set_cli_options(&mut config, &matches);
+        fs::remove_dir_all(folder).unwrap();
+        assert_eq!(config.get_threads(), 8);
+        assert!(config.is_include_smali());
+    }
 }
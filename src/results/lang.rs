@@ -0,0 +1,172 @@
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use {Error, Result};
+
+/// Human-readable texts of the reports, with their key and their English and Spanish
+/// translations. Only the texts of the reports are translated, not the findings.
+const MESSAGES: [(&'static str, &'static str, &'static str); 50] =
+    [("report_title", "Vulnerability report", "Informe de vulnerabilidades"),
+     ("report_header",
+      "S.U.P.E.R. Android Analyzer Report",
+      "Informe de S.U.P.E.R. Android Analyzer"),
+     ("report_intro",
+      "This is the vulnerability report for the android application",
+      "Este es el informe de vulnerabilidades de la aplicación android"),
+     ("report_date", "Report generated on", "Informe generado el"),
+     ("application_data", "Application data", "Datos de la aplicación"),
+     ("label", "Label", "Etiqueta"),
+     ("description", "Description", "Descripción"),
+     ("package", "Package", "Paquete"),
+     ("version", "Version", "Versión"),
+     ("version_number", "Version number", "Número de versión"),
+     ("min_sdk", "Minimum SDK version", "Versión mínima del SDK"),
+     ("target_sdk", "Target SDK", "SDK objetivo"),
+     ("fingerprints", "Fingerprints", "Huellas digitales"),
+     ("source_code", "Source code", "Código fuente"),
+     ("check_source_code", "Check source code", "Ver el código fuente"),
+     ("total_vulnerabilities",
+      "Total vulnerabilities found",
+      "Total de vulnerabilidades encontradas"),
+     ("application_score", "Application score", "Puntuación de la aplicación"),
+     ("findings_density",
+      "Findings per 1000 lines of code",
+      "Hallazgos por cada 1000 líneas de código"),
+     ("lines_analyzed", "lines analyzed", "líneas analizadas"),
+     ("truncated_findings",
+      "Findings not reported because the maximum was reached",
+      "Hallazgos no informados por haber alcanzado el máximo"),
+     ("timed_out_files",
+      "Files not analyzed because they took too long",
+      "Archivos no analizados por tardar demasiado"),
+     ("vulnerabilities", "Vulnerabilities", "Vulnerabilidades"),
+     ("critical", "Critical", "Crítica"),
+     ("high", "High", "Alta"),
+     ("medium", "Medium", "Media"),
+     ("low", "Low", "Baja"),
+     ("warning", "Warning", "Aviso"),
+     ("warnings", "Warnings", "Avisos"),
+     ("critical_vulnerabilities",
+      "Critical criticity vulnerabilities",
+      "Vulnerabilidades de criticidad crítica"),
+     ("high_vulnerabilities",
+      "High criticity vulnerabilities",
+      "Vulnerabilidades de criticidad alta"),
+     ("medium_vulnerabilities",
+      "Medium criticity vulnerabilities",
+      "Vulnerabilidades de criticidad media"),
+     ("low_vulnerabilities",
+      "Low criticity vulnerabilities",
+      "Vulnerabilidades de criticidad baja"),
+     ("display_vulnerability", "Display vulnerability", "Mostrar vulnerabilidad"),
+     ("collapse_vulnerability", "Collapse vulnerability", "Ocultar vulnerabilidad"),
+     ("message", "Message", "Mensaje"),
+     ("confidence", "Confidence", "Confianza"),
+     ("source_file", "File", "Archivo"),
+     ("line", "Line", "Línea"),
+     ("lines", "Lines", "Líneas"),
+     ("affected_code", "Affected code", "Código afectado"),
+     ("in", "in", "en"),
+     ("file", "file", "archivo"),
+     ("files", "files", "archivos"),
+     ("top", "Top", "Inicio"),
+     ("baseline_written",
+      "{count} findings were written to the baseline {path}.",
+      "{count} hallazgos se escribieron en la línea base {path}."),
+     ("baseline_suppressed",
+      "{count} findings were suppressed by the baseline {path}.",
+      "{count} hallazgos fueron suprimidos por la línea base {path}."),
+     ("compared_with", "Compared with {path}:", "Comparado con {path}:"),
+     ("comparison_summary",
+      "{new} new, {existing} existing and {fixed} fixed findings.",
+      "{new} hallazgos nuevos, {existing} existentes y {fixed} corregidos."),
+     ("selection_summary",
+      "{count} findings were left out by the selection.",
+      "{count} hallazgos quedaron fuera de la selección."),
+     ("truncated_summary",
+      "{count} findings were not reported because the maximum of {max} was reached.",
+      "{count} hallazgos no se informaron porque se alcanzó el máximo de {max}.")];
+
+/// Language of the human-readable texts of the reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    En,
+    Es,
+}
+
+impl Lang {
+    /// Gets the text of the given message key in the language. Unknown keys are returned as they
+    /// are, so that a missing message is visible in the report.
+    pub fn get<'k>(&self, key: &'k str) -> &'k str {
+        match MESSAGES.iter().find(|&&(k, _, _)| k == key) {
+            Some(&(_, en, es)) => {
+                match *self {
+                    Lang::En => en,
+                    Lang::Es => es,
+                }
+            }
+            None => key,
+        }
+    }
+
+    /// Gets the text of the given message key in the language, replacing each `{name}` in it
+    /// with the value of that name.
+    pub fn format(&self, key: &str, values: &[(&str, &Display)]) -> String {
+        let mut text = String::from(self.get(key));
+        for &(name, value) in values {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+impl Default for Lang {
+    fn default() -> Lang {
+        Lang::En
+    }
+}
+
+impl Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl FromStr for Lang {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Lang> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "es" => Ok(Lang::Es),
+            _ => Err(Error::ParseError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{Lang, MESSAGES};
+
+    #[test]
+    fn it_lang() {
+        assert_eq!(Lang::from_str("ES").unwrap(), Lang::Es);
+        assert!(Lang::from_str("fr").is_err());
+        assert_eq!(Lang::default(), Lang::En);
+        assert_eq!(Lang::Es.to_string(), "es");
+
+        assert_eq!(Lang::En.get("vulnerabilities"), "Vulnerabilities");
+        assert_eq!(Lang::Es.get("vulnerabilities"), "Vulnerabilidades");
+        assert_eq!(Lang::Es.get("unknown_message"), "unknown_message");
+        assert_eq!(Lang::Es.format("truncated_summary", &[("count", &3), ("max", &10)]),
+                   "3 hallazgos no se informaron porque se alcanzó el máximo de 10.");
+
+        // Every message is translated, and its key is unique
+        for (i, &(key, en, es)) in MESSAGES.iter().enumerate() {
+            assert!(!en.is_empty() && !es.is_empty());
+            assert!(MESSAGES[i + 1..].iter().all(|&(k, _, _)| k != key));
+        }
+    }
+}
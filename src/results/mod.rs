@@ -1,22 +1,102 @@
 use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::{Read, Write};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, BTreeMap};
 use std::path::Path;
 use std::borrow::Borrow;
 use std::slice::Iter;
+use std::str::FromStr;
+use std::fmt;
+use std::fmt::Display;
 
-use serde_json::builder::ObjectBuilder;
+use serde_json;
+use serde_json::builder::{ArrayBuilder, ObjectBuilder};
+use serde_json::value::Value;
 use chrono::{Local, Datelike};
+use colored::Colorize;
 use rustc_serialize::hex::ToHex;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 
 mod utils;
+mod lang;
+mod select;
 
-pub use self::utils::{Benchmark, Vulnerability};
+pub use self::utils::{Benchmark, Vulnerability, VulnerabilityBuilder};
+pub use self::lang::Lang;
+pub use self::select::Selector;
 use self::utils::FingerPrint;
 
 use {Error, Config, Result, Criticity, print_error, print_warning, file_exists, copy_folder};
 
+/// Maximum length of the bars of the criticity summary.
+const SUMMARY_BAR_WIDTH: usize = 40;
+
+/// Format of the report written to the output given in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Html,
+    Csv,
+    Sarif,
+    Txt,
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<ReportFormat> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            "html" => Ok(ReportFormat::Html),
+            "csv" => Ok(ReportFormat::Csv),
+            "sarif" => Ok(ReportFormat::Sarif),
+            "txt" => Ok(ReportFormat::Txt),
+            _ => Err(Error::ParseError),
+        }
+    }
+}
+
+/// All the report formats, written to the output folder when no format is selected.
+const REPORT_FORMATS: [ReportFormat; 5] = [ReportFormat::Json,
+                                           ReportFormat::Html,
+                                           ReportFormat::Csv,
+                                           ReportFormat::Sarif,
+                                           ReportFormat::Txt];
+
+/// Classification of the findings of an analysis against the ones of a previous report, using
+/// their fingerprints.
+#[derive(Debug, Default)]
+pub struct Comparison {
+    new: BTreeSet<String>,
+    existing: BTreeSet<String>,
+    fixed: BTreeSet<String>,
+}
+
+impl Comparison {
+    /// Gets the fingerprints of the findings that were not in the previous report.
+    pub fn get_new(&self) -> &BTreeSet<String> {
+        &self.new
+    }
+
+    /// Gets the fingerprints of the findings that were also in the previous report.
+    pub fn get_existing(&self) -> &BTreeSet<String> {
+        &self.existing
+    }
+
+    /// Gets the fingerprints of the findings of the previous report that were not found again.
+    pub fn get_fixed(&self) -> &BTreeSet<String> {
+        &self.fixed
+    }
+}
+
+#[derive(Default)]
 pub struct Results {
     app_package: String,
     app_label: String,
@@ -32,6 +112,15 @@ pub struct Results {
     high: BTreeSet<Vulnerability>,
     critical: BTreeSet<Vulnerability>,
     benchmarks: Vec<Benchmark>,
+    timed_out_files: Vec<String>,
+    known_good_files: Vec<String>,
+    lines_analyzed: usize,
+    rule_match_counts: Vec<(String, usize)>,
+    rule_coverage: Vec<(String, Option<String>)>,
+    rules_files: Vec<(String, String)>,
+    score_weights: BTreeMap<Criticity, f64>,
+    truncated_findings: usize,
+    lang: Lang,
 }
 
 impl Results {
@@ -48,14 +137,19 @@ impl Results {
                 }
             }
 
-            let fingerprint = match FingerPrint::new(config) {
-                Ok(f) => f,
-                Err(e) => {
-                    print_error(format!("An error occurred when trying to fingerprint the \
-                                         application: {}",
-                                        e),
-                                config.is_verbose());
-                    return None;
+            // There is no APK to fingerprint when analyzing an already decompiled source folder.
+            let fingerprint = if config.get_source_folder().is_some() {
+                FingerPrint::default()
+            } else {
+                match FingerPrint::new(config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        print_error(format!("An error occurred when trying to fingerprint the \
+                                             application: {}",
+                                            e),
+                                    config.is_verbose());
+                        return None;
+                    }
                 }
             };
             if config.is_verbose() {
@@ -84,6 +178,15 @@ impl Results {
                 } else {
                     Vec::with_capacity(0)
                 },
+                timed_out_files: Vec::new(),
+                known_good_files: Vec::new(),
+                lines_analyzed: 0,
+                rule_match_counts: Vec::new(),
+                rule_coverage: Vec::new(),
+                rules_files: Vec::new(),
+                score_weights: config.get_score_weights().clone(),
+                truncated_findings: 0,
+                lang: config.get_lang(),
             })
         } else {
             if config.is_verbose() {
@@ -122,6 +225,24 @@ impl Results {
         self.app_target_sdk = Some(sdk);
     }
 
+    /// Adds the findings of another analysis of the same application, such as one run in a
+    /// different thread.
+    pub fn merge_vulnerabilities(&mut self, other: Results) {
+        let vulnerabilities = other.warnings
+            .into_iter()
+            .chain(other.low)
+            .chain(other.medium)
+            .chain(other.high)
+            .chain(other.critical);
+        for vuln in vulnerabilities {
+            self.add_vulnerability(vuln);
+        }
+    }
+
+    /// Adds a finding to the results.
+    ///
+    /// The findings can be built with a `VulnerabilityBuilder`. Findings that are equal to one
+    /// already added are only reported once.
     pub fn add_vulnerability(&mut self, vuln: Vulnerability) {
         match vuln.get_criticity() {
             Criticity::Warning => {
@@ -142,6 +263,46 @@ impl Results {
         }
     }
 
+    /// Gets the number of findings in the results, including warnings.
+    pub fn get_total_findings(&self) -> usize {
+        self.warnings.len() + self.low.len() + self.medium.len() + self.high.len() +
+        self.critical.len()
+    }
+
+    /// Removes the least critical findings until there are at most `max_findings`, counting the
+    /// removed ones as not reported.
+    ///
+    /// It must be called once the findings have been filtered by the baseline and the
+    /// selection, so that the maximum only counts the findings that would be reported.
+    pub fn truncate_findings(&mut self, max_findings: usize) {
+        let mut excess = self.get_total_findings().saturating_sub(max_findings);
+        self.truncated_findings += excess;
+        for set in &mut [&mut self.warnings,
+                         &mut self.low,
+                         &mut self.medium,
+                         &mut self.high,
+                         &mut self.critical] {
+            let keep = set.len().saturating_sub(excess);
+            excess -= set.len() - keep;
+            **set = set.iter().take(keep).cloned().collect();
+        }
+    }
+
+    /// Gets the number of findings that were not reported because the maximum was reached.
+    pub fn get_truncated_findings(&self) -> usize {
+        self.truncated_findings
+    }
+
+    /// Gets the fingerprint of the analyzed APK.
+    pub fn get_app_fingerprint(&self) -> &FingerPrint {
+        &self.app_fingerprint
+    }
+
+    /// Adds a rule file used in the analysis, with the SHA-256 hash of its contents.
+    pub fn add_rules_file(&mut self, file: &str, sha256: &str) {
+        self.rules_files.push((String::from(file), String::from(sha256)));
+    }
+
     pub fn add_benchmark(&mut self, bench: Benchmark) {
         self.benchmarks.push(bench);
     }
@@ -150,7 +311,297 @@ impl Results {
         self.benchmarks.iter()
     }
 
+    pub fn add_timed_out_file(&mut self, file: &str) {
+        self.timed_out_files.push(String::from(file));
+    }
+
+    /// Adds a file that was not analyzed because its hash is in the known-good list.
+    pub fn add_known_good_file(&mut self, file: &str) {
+        self.known_good_files.push(String::from(file));
+    }
+
+    pub fn set_lines_analyzed(&mut self, lines: usize) {
+        self.lines_analyzed = lines;
+    }
+
+    /// Adds the number of findings of a rule. The counts are reported in the order they are added.
+    pub fn add_rule_match_count(&mut self, rule_id: &str, count: usize) {
+        self.rule_match_counts.push((String::from(rule_id), count));
+    }
+
+    /// Adds whether a rule ran in the application, with the reason why it was skipped if it did
+    /// not. The rules are reported in the order they are added.
+    pub fn add_rule_coverage(&mut self, rule_id: &str, skip_reason: Option<&str>) {
+        self.rule_coverage.push((String::from(rule_id), skip_reason.map(String::from)));
+    }
+
+    /// Gets the number of findings of the given criticity per 1000 analyzed lines of code.
+    ///
+    /// If no lines were analyzed, the density will be 0.
+    pub fn get_density(&self, criticity: Criticity) -> f64 {
+        if self.lines_analyzed == 0 {
+            return 0_f64;
+        }
+        let count = match criticity {
+            Criticity::Warning => self.warnings.len(),
+            Criticity::Low => self.low.len(),
+            Criticity::Medium => self.medium.len(),
+            Criticity::High => self.high.len(),
+            Criticity::Critical => self.critical.len(),
+        };
+        count as f64 * 1000_f64 / self.lines_analyzed as f64
+    }
+
+    /// Gets the score of the application, weighting its findings by their criticity.
+    ///
+    /// The score is computed as `Σ weight(criticity) × findings(criticity)`, using the weights in
+    /// the configuration. It is not capped, so it grows with the number of findings. If a
+    /// criticity has no weight, its findings do not count.
+    pub fn get_score(&self) -> f64 {
+        let counts = [(Criticity::Warning, self.warnings.len()),
+                      (Criticity::Low, self.low.len()),
+                      (Criticity::Medium, self.medium.len()),
+                      (Criticity::High, self.high.len()),
+                      (Criticity::Critical, self.critical.len())];
+        counts.iter()
+            .map(|&(criticity, count)| {
+                self.score_weights.get(&criticity).cloned().unwrap_or(0_f64) * count as f64
+            })
+            .sum()
+    }
+
+    /// Gets the number of distinct files with at least a finding of the given criticity.
+    ///
+    /// A file with findings of several criticities is counted in each of them. Findings without a
+    /// file, such as the ones of the manifest checks, are not counted.
+    pub fn get_affected_files(&self, criticity: Criticity) -> usize {
+        let vulns = match criticity {
+            Criticity::Warning => &self.warnings,
+            Criticity::Low => &self.low,
+            Criticity::Medium => &self.medium,
+            Criticity::High => &self.high,
+            Criticity::Critical => &self.critical,
+        };
+        vulns.iter().filter_map(|v| v.get_file()).collect::<BTreeSet<_>>().len()
+    }
+
+    /// Gets a bar chart of the number of findings of each criticity, from the most critical to
+    /// the least critical, with the number of files they were found in.
+    ///
+    /// The longest bar is `SUMMARY_BAR_WIDTH` characters long. Without color, the bars are drawn
+    /// with `#`, so that the chart can be read when the output is not a terminal.
+    pub fn get_criticity_summary(&self, color: bool) -> String {
+        let counts = [(Criticity::Critical, self.critical.len()),
+                      (Criticity::High, self.high.len()),
+                      (Criticity::Medium, self.medium.len()),
+                      (Criticity::Low, self.low.len()),
+                      (Criticity::Warning, self.warnings.len())];
+        let max = counts.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        let mut summary = String::new();
+        for &(criticity, count) in &counts {
+            // Any finding gets at least a character, rounding up
+            let width = if count == 0 {
+                0
+            } else {
+                (count * SUMMARY_BAR_WIDTH + max - 1) / max
+            };
+            let label = format!("{:<8}",
+                                self.lang.get(&criticity.to_string()).to_lowercase());
+            let count = match self.get_affected_files(criticity) {
+                0 => count.to_string(),
+                1 => format!("{} {} 1 {}", count, self.lang.get("in"), self.lang.get("file")),
+                files => {
+                    format!("{} {} {} {}",
+                            count,
+                            self.lang.get("in"),
+                            files,
+                            self.lang.get("files"))
+                }
+            };
+            if color {
+                let bar: String = (0..width).map(|_| '█').collect();
+                let (label, bar) = match criticity {
+                    Criticity::Critical => (label.red().bold(), bar.red()),
+                    Criticity::High => (label.bright_red(), bar.bright_red()),
+                    Criticity::Medium => (label.yellow(), bar.yellow()),
+                    Criticity::Low => (label.cyan(), bar.cyan()),
+                    Criticity::Warning => (label.normal(), bar.normal()),
+                };
+                summary.push_str(&format!("{} {} {}\n", label, bar, count));
+            } else {
+                let bar: String = (0..width).map(|_| '#').collect();
+                summary.push_str(&format!("{} {} {}\n", label, bar, count));
+            }
+        }
+        summary
+    }
+
+    /// Gets the highest criticity of the findings, if there is any.
+    pub fn get_highest_criticity(&self) -> Option<Criticity> {
+        if !self.critical.is_empty() {
+            Some(Criticity::Critical)
+        } else if !self.high.is_empty() {
+            Some(Criticity::High)
+        } else if !self.medium.is_empty() {
+            Some(Criticity::Medium)
+        } else if !self.low.is_empty() {
+            Some(Criticity::Low)
+        } else if !self.warnings.is_empty() {
+            Some(Criticity::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Compares the findings with the ones of a previous JSON report.
+    ///
+    /// Findings with the same fingerprint are considered the same, so a finding found more than
+    /// once in the same file and code is only counted once.
+    pub fn compare<R: Read>(&self, previous: R) -> Result<Comparison> {
+        let previous: Value = try!(serde_json::from_reader(previous));
+        let mut previous_fingerprints = BTreeSet::new();
+        for criticity in &["warnings", "low", "medium", "high", "critical"] {
+            let vulnerabilities = match previous.find(criticity).and_then(|v| v.as_array()) {
+                Some(v) => v,
+                None => continue,
+            };
+            for vuln in vulnerabilities {
+                match vuln.find("fingerprint").and_then(|f| f.as_str()) {
+                    Some(f) => {
+                        previous_fingerprints.insert(String::from(f));
+                    }
+                    None => return Err(Error::ParseError),
+                }
+            }
+        }
+
+        let mut comparison: Comparison = Default::default();
+        for vuln in self.get_all_vulnerabilities() {
+            let fingerprint = vuln.get_fingerprint();
+            if previous_fingerprints.contains(&fingerprint) {
+                comparison.existing.insert(fingerprint);
+            } else {
+                comparison.new.insert(fingerprint);
+            }
+        }
+        comparison.fixed = previous_fingerprints.difference(&comparison.existing)
+            .cloned()
+            .collect();
+        Ok(comparison)
+    }
+
+    /// Gets the fingerprints of all the findings.
+    pub fn get_fingerprints(&self) -> BTreeSet<String> {
+        self.get_all_vulnerabilities().iter().map(|v| v.get_fingerprint()).collect()
+    }
+
+    /// Removes the findings that were already in the previous report of the comparison.
+    pub fn remove_existing(&mut self, comparison: &Comparison) {
+        for vulnerabilities in &mut [&mut self.warnings,
+                                     &mut self.low,
+                                     &mut self.medium,
+                                     &mut self.high,
+                                     &mut self.critical] {
+            let new = vulnerabilities.iter()
+                .filter(|v| !comparison.existing.contains(&v.get_fingerprint()))
+                .cloned()
+                .collect();
+            **vulnerabilities = new;
+        }
+    }
+
+    /// Writes a baseline with all the findings, so that they can be suppressed in later analyses
+    /// with `suppress_baseline()`.
+    ///
+    /// The baseline is a JSON array with the rule ID, file, line and fingerprint of each finding,
+    /// sorted by them, so that the same findings always give the same file.
+    pub fn write_baseline<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut entries: Vec<_> = self.get_all_vulnerabilities()
+            .into_iter()
+            .map(|v| {
+                (v.get_rule_id(),
+                 v.get_file().map(|f| f.to_string_lossy().into_owned()),
+                 v.get_start_line(),
+                 v.get_fingerprint())
+            })
+            .collect();
+        entries.sort();
+        entries.dedup();
+
+        let mut baseline = ArrayBuilder::new();
+        for (rule_id, file, line, fingerprint) in entries {
+            baseline = baseline.push_object(|builder| {
+                builder.insert("rule_id", rule_id)
+                    .insert("file", file)
+                    .insert("line", line)
+                    .insert("fingerprint", fingerprint)
+            });
+        }
+        let baseline = try!(serde_json::to_string_pretty(&baseline.build()));
+        try!(writer.write_all(baseline.as_bytes()));
+        try!(writer.write_all(b"\n"));
+        Ok(())
+    }
+
+    /// Removes the findings whose fingerprint is in the given baseline, written by
+    /// `write_baseline()`, and returns how many were removed.
+    pub fn suppress_baseline<R: Read>(&mut self, baseline: R) -> Result<usize> {
+        let baseline: Value = try!(serde_json::from_reader(baseline));
+        let entries = match baseline.as_array() {
+            Some(e) => e,
+            None => return Err(Error::ParseError),
+        };
+        let mut fingerprints = BTreeSet::new();
+        for entry in entries {
+            match entry.find("fingerprint").and_then(|f| f.as_str()) {
+                Some(f) => {
+                    fingerprints.insert(String::from(f));
+                }
+                None => return Err(Error::ParseError),
+            }
+        }
+
+        let total = self.get_total_findings();
+        for vulnerabilities in &mut [&mut self.warnings,
+                                     &mut self.low,
+                                     &mut self.medium,
+                                     &mut self.high,
+                                     &mut self.critical] {
+            let kept = vulnerabilities.iter()
+                .filter(|v| !fingerprints.contains(&v.get_fingerprint()))
+                .cloned()
+                .collect();
+            **vulnerabilities = kept;
+        }
+        Ok(total - self.get_total_findings())
+    }
+
+    /// Removes the findings that are not selected by the given selector, and returns how many
+    /// were removed.
+    pub fn select(&mut self, selector: &Selector) -> usize {
+        let total = self.get_total_findings();
+        for vulnerabilities in &mut [&mut self.warnings,
+                                     &mut self.low,
+                                     &mut self.medium,
+                                     &mut self.high,
+                                     &mut self.critical] {
+            let kept = vulnerabilities.iter()
+                .filter(|v| selector.matches(v))
+                .cloned()
+                .collect();
+            **vulnerabilities = kept;
+        }
+        total - self.get_total_findings()
+    }
+
+    /// Generates the reports in the results folder and in the outputs of the configuration.
+    ///
+    /// Several report formats can only be written to an output folder, so it returns an error if
+    /// there is more than one and no output folder, instead of writing only some of them.
     pub fn generate_report(&self, config: &Config) -> Result<()> {
+        if config.get_output_dir().is_none() && config.get_report_formats().len() > 1 {
+            return Err(Error::Config);
+        }
         let path = format!("{}/{}", config.get_results_folder(), config.get_app_id());
         if !file_exists(&path) || config.is_force() {
             if file_exists(&path) {
@@ -177,6 +628,68 @@ impl Results {
                 println!("");
             }
 
+            if let Some(gz_path) = config.get_output_json_gz() {
+                try!(self.generate_json_gz_report(gz_path));
+
+                if config.is_verbose() {
+                    println!("Compressed JSON report generated in {}.", gz_path);
+                    println!("");
+                }
+            }
+
+            if let Some(txt_path) = config.get_output_txt() {
+                let mut f = try!(File::create(txt_path));
+                try!(self.write_txt_report(&mut f));
+
+                if config.is_verbose() {
+                    println!("Text report generated in {}.", txt_path);
+                    println!("");
+                }
+            }
+
+            if let Some(by_file_path) = config.get_output_by_file() {
+                let mut f = try!(File::create(by_file_path));
+                try!(f.write_all(&format!("{:?}", self.get_json_by_file_report()).into_bytes()));
+
+                if config.is_verbose() {
+                    println!("JSON report grouped by file generated in {}.", by_file_path);
+                    println!("");
+                }
+            }
+
+            if let Some(dir) = config.get_output_dir() {
+                let formats: Vec<_> = config.get_report_formats().cloned().collect();
+                let formats = if formats.is_empty() {
+                    &REPORT_FORMATS[..]
+                } else {
+                    &formats[..]
+                };
+                try!(self.write_reports_to_dir(dir, formats));
+
+                if config.is_verbose() {
+                    println!("{} reports generated in {}.", formats.len(), dir);
+                    println!("");
+                }
+            } else if let Some(&format) = config.get_report_formats().next() {
+                match config.get_output() {
+                    Some(output) => {
+                        let mut f = try!(File::create(output));
+                        try!(self.write_report(format, &mut f));
+
+                        if config.is_verbose() {
+                            println!("{} report generated in {}.",
+                                     format.to_string().to_uppercase(),
+                                     output);
+                            println!("");
+                        }
+                    }
+                    None => {
+                        let stdout = io::stdout();
+                        try!(self.write_report(format, &mut stdout.lock()));
+                    }
+                }
+            }
+
             try!(self.generate_html_report(config));
 
             if config.is_verbose() {
@@ -187,6 +700,267 @@ impl Results {
         Ok(())
     }
 
+    /// Writes the report in each of the given formats to the given folder, creating it if
+    /// needed. The reports are named after their format, such as `report.json` or
+    /// `report.sarif`, so that the same files are overwritten in each analysis.
+    pub fn write_reports_to_dir<P: AsRef<Path>>(&self,
+                                                dir: P,
+                                                formats: &[ReportFormat])
+                                                -> Result<()> {
+        try!(fs::create_dir_all(&dir));
+        for &format in formats {
+            let mut f = try!(File::create(dir.as_ref().join(format!("report.{}", format))));
+            try!(self.write_report(format, &mut f));
+        }
+        Ok(())
+    }
+
+    /// Writes the report in the given format.
+    ///
+    /// The HTML report written this way is only the main page, without the source code pages and
+    /// the styles of the report generated in the results folder.
+    pub fn write_report<W: Write>(&self, format: ReportFormat, writer: &mut W) -> Result<()> {
+        match format {
+            ReportFormat::Json => {
+                try!(writer.write_all(&format!("{:?}", self.get_json_report()).into_bytes()))
+            }
+            ReportFormat::Html => try!(self.write_html_report(writer)),
+            ReportFormat::Csv => try!(self.write_csv_report(writer)),
+            ReportFormat::Txt => try!(self.write_txt_report(writer)),
+            ReportFormat::Sarif => {
+                try!(writer.write_all(&format!("{:?}", self.get_sarif_report()).into_bytes()))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets all the vulnerabilities, from the most critical to the least critical.
+    fn get_all_vulnerabilities(&self) -> Vec<&Vulnerability> {
+        self.critical
+            .iter()
+            .chain(self.high.iter())
+            .chain(self.medium.iter())
+            .chain(self.low.iter())
+            .chain(self.warnings.iter())
+            .collect()
+    }
+
+    /// Writes a line per finding, with the fields in the header.
+    ///
+    /// If there are benchmarks, a `benchmark` line is written after the findings for each of
+    /// them, with its label as name and its duration in milliseconds as description.
+    fn write_csv_report<W: Write>(&self, f: &mut W) -> Result<()> {
+        try!(f.write_all(b"criticity,confidence,category,rule_id,name,file,start_line,end_line,\
+                           start_col,end_col,description\n"));
+        for vuln in self.get_all_vulnerabilities() {
+            let fields = [format!("{}", vuln.get_criticity()),
+                          format!("{}", vuln.get_confidence()),
+                          String::from(vuln.get_category().unwrap_or("")),
+                          String::from(vuln.get_rule_id().unwrap_or("")),
+                          String::from(vuln.get_name()),
+                          vuln.get_file()
+                              .map(|f| f.to_string_lossy().into_owned())
+                              .unwrap_or(String::new()),
+                          vuln.get_start_line().map(|l| l.to_string()).unwrap_or(String::new()),
+                          vuln.get_end_line().map(|l| l.to_string()).unwrap_or(String::new()),
+                          vuln.get_start_col().map(|c| c.to_string()).unwrap_or(String::new()),
+                          vuln.get_end_col().map(|c| c.to_string()).unwrap_or(String::new()),
+                          String::from(vuln.get_description())];
+            let line = fields.iter()
+                .map(|f| Results::csv_escape(f))
+                .collect::<Vec<_>>()
+                .join(",");
+            try!(f.write_all(&format!("{}\n", line).into_bytes()));
+        }
+        for bench in &self.benchmarks {
+            try!(f.write_all(&format!("benchmark,,,,{},,,,,,{} ms\n",
+                                      Results::csv_escape(bench.get_label()),
+                                      bench.get_millis())
+                .into_bytes()));
+        }
+
+        Ok(())
+    }
+
+    /// Writes a line per finding, with its criticity, rule ID, location and label separated by
+    /// tabs.
+    ///
+    /// The findings are written from the most critical to the least critical, and then sorted by
+    /// file, line and label. The report has no timestamps or other data that changes between runs,
+    /// so two reports of the same code are identical and can be compared with `diff`.
+    fn write_txt_report<W: Write>(&self, f: &mut W) -> Result<()> {
+        for vuln in self.get_all_vulnerabilities() {
+            let location = match (vuln.get_file(), vuln.get_start_line()) {
+                (Some(file), Some(line)) => format!("{}:{}", file.display(), line),
+                (Some(file), None) => format!("{}", file.display()),
+                (None, _) => String::from("-"),
+            };
+            let fields = [format!("{}", vuln.get_criticity()),
+                          String::from(vuln.get_rule_id().unwrap_or("-")),
+                          location,
+                          String::from(vuln.get_name())];
+            let line = fields.iter()
+                .map(|f| f.replace(|c| c == '\t' || c == '\n' || c == '\r', " "))
+                .collect::<Vec<_>>()
+                .join("\t");
+            try!(f.write_all(&format!("{}\n", line).into_bytes()));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the JSON report with the findings grouped by file, for editor integrations.
+    ///
+    /// It is an object with a key per file, each with the findings of the file sorted by line.
+    /// Findings without a file, and files without findings, are not included.
+    fn get_json_by_file_report(&self) -> Value {
+        let mut files = BTreeMap::new();
+        for vuln in self.get_all_vulnerabilities() {
+            if let Some(file) = vuln.get_file() {
+                files.entry(format!("{}", file.display())).or_insert_with(Vec::new).push(vuln);
+            }
+        }
+
+        let mut builder = ObjectBuilder::new();
+        for (file, mut vulnerabilities) in files {
+            vulnerabilities.sort_by_key(|v| (v.get_start_line(), v.get_start_col()));
+            builder = builder.insert_array(file, |builder| {
+                let mut builder = builder;
+                for vuln in vulnerabilities {
+                    builder = builder.push(vuln);
+                }
+                builder
+            });
+        }
+        builder.build()
+    }
+
+    /// Gets the report in the SARIF 2.1.0 format, used by code scanning tools.
+    ///
+    /// If there are benchmarks, they are added to the properties of the run.
+    fn get_sarif_report(&self) -> Value {
+        let vulnerabilities = self.get_all_vulnerabilities();
+        let mut rules = BTreeSet::new();
+        for vuln in &vulnerabilities {
+            if let Some(id) = vuln.get_rule_id() {
+                let _ = rules.insert(id);
+            }
+        }
+
+        ObjectBuilder::new()
+            .insert("$schema",
+                    "https://schemastore.azurewebsites.net/schemas/json/sarif-2.1.0.json")
+            .insert("version", "2.1.0")
+            .insert_array("runs", |builder| {
+                builder.push_object(|builder| {
+                    let builder = builder.insert_object("tool", |builder| {
+                            builder.insert_object("driver", |builder| {
+                                builder.insert("name", "SUPER Android Analyzer")
+                                    .insert("version", env!("CARGO_PKG_VERSION"))
+                                    .insert("informationUri", "http://superanalyzer.rocks")
+                                    .insert_array("rules", |builder| {
+                                        let mut builder = builder;
+                                        for id in &rules {
+                                            builder = builder.push_object(|builder| {
+                                                builder.insert("id", *id)
+                                            });
+                                        }
+                                        builder
+                                    })
+                            })
+                        })
+                        .insert("columnKind", "unicodeCodePoints")
+                        .insert_array("results", |builder| {
+                            let mut builder = builder;
+                            for vuln in &vulnerabilities {
+                                builder = builder.push(Results::get_sarif_result(vuln));
+                            }
+                            builder
+                        });
+                    if self.benchmarks.is_empty() {
+                        builder
+                    } else {
+                        builder.insert_object("properties", |builder| {
+                            builder.insert_array("benchmarks", |builder| {
+                                let mut builder = builder;
+                                for bench in &self.benchmarks {
+                                    builder = builder.push(bench);
+                                }
+                                builder
+                            })
+                        })
+                    }
+                })
+            })
+            .build()
+    }
+
+    /// Gets the URI of the file of a finding. Absolute paths are given as `file://` URIs.
+    fn get_sarif_uri(file: &Path) -> String {
+        let uri = file.to_string_lossy().replace('\\', "/");
+        if !file.is_absolute() {
+            uri
+        } else if uri.starts_with('/') {
+            format!("file://{}", uri)
+        } else {
+            format!("file:///{}", uri)
+        }
+    }
+
+    fn get_sarif_result(vuln: &Vulnerability) -> Value {
+        let level = match vuln.get_criticity() {
+            Criticity::Critical | Criticity::High => "error",
+            Criticity::Medium => "warning",
+            Criticity::Low | Criticity::Warning => "note",
+        };
+        let mut builder = ObjectBuilder::new()
+            .insert("level", level)
+            .insert_object("message", |builder| {
+                builder.insert("text",
+                               format!("{}: {}", vuln.get_name(), vuln.get_message()))
+            });
+        if let Some(id) = vuln.get_rule_id() {
+            builder = builder.insert("ruleId", id);
+        }
+        if let Some(file) = vuln.get_file() {
+            builder = builder.insert_array("locations", |builder| {
+                builder.push_object(|builder| {
+                    builder.insert_object("physicalLocation", |builder| {
+                        let builder = builder.insert_object("artifactLocation", |builder| {
+                            builder.insert("uri", Results::get_sarif_uri(file))
+                        });
+                        match (vuln.get_start_line(), vuln.get_end_line()) {
+                            (Some(start), Some(end)) => {
+                                builder.insert_object("region", |builder| {
+                                    let builder = builder.insert("startLine", start as u64)
+                                        .insert("endLine", end as u64);
+                                    match (vuln.get_start_col(), vuln.get_end_col()) {
+                                        (Some(start), Some(end)) => {
+                                            builder.insert("startColumn", start as u64)
+                                                .insert("endColumn", end as u64)
+                                        }
+                                        _ => builder,
+                                    }
+                                })
+                            }
+                            _ => builder,
+                        }
+                    })
+                })
+            });
+        }
+        builder.build()
+    }
+
+    fn csv_escape(field: &str) -> String {
+        if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            String::from(field)
+        }
+    }
+
     fn generate_json_report(&self, config: &Config) -> Result<()> {
         if config.is_verbose() {
             println!("Starting JSON report generation. First we create the file.")
@@ -198,7 +972,22 @@ impl Results {
             println!("The report file has been created. Now it's time to fill it.")
         }
 
-        let report = ObjectBuilder::new()
+        try!(f.write_all(&format!("{:?}", self.get_json_report()).into_bytes()));
+
+        Ok(())
+    }
+
+    fn generate_json_gz_report<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let f = try!(File::create(path));
+        let mut encoder = GzEncoder::new(f, Compression::Default);
+        try!(encoder.write_all(&format!("{:?}", self.get_json_report()).into_bytes()));
+        try!(encoder.finish());
+
+        Ok(())
+    }
+
+    fn get_json_report(&self) -> Value {
+        ObjectBuilder::new()
             .insert("label", self.app_label.as_str())
             .insert("description", self.app_description.as_str())
             .insert("package", self.app_package.as_str())
@@ -239,11 +1028,82 @@ impl Results {
                 }
                 builder
             })
-            .build();
-
-        try!(f.write_all(&format!("{:?}", report).into_bytes()));
-
-        Ok(())
+            .insert_array("rules_files", |builder| {
+                let mut builder = builder;
+                for &(ref file, ref sha256) in &self.rules_files {
+                    builder = builder.push_object(|builder| {
+                        builder.insert("file", file.as_str()).insert("sha256", sha256.as_str())
+                    });
+                }
+                builder
+            })
+            .insert_array("timed_out_files", |builder| {
+                let mut builder = builder;
+                for file in &self.timed_out_files {
+                    builder = builder.push(file);
+                }
+                builder
+            })
+            .insert_array("known_good_files", |builder| {
+                let mut builder = builder;
+                for file in &self.known_good_files {
+                    builder = builder.push(file);
+                }
+                builder
+            })
+            .insert_array("benchmarks", |builder| {
+                let mut builder = builder;
+                for bench in &self.benchmarks {
+                    builder = builder.push(bench);
+                }
+                builder
+            })
+            .insert_object("affected_files", |builder| {
+                let mut builder = builder;
+                for &criticity in &[Criticity::Warning,
+                                    Criticity::Low,
+                                    Criticity::Medium,
+                                    Criticity::High,
+                                    Criticity::Critical] {
+                    builder = builder.insert(criticity.to_string(),
+                                             self.get_affected_files(criticity) as u64);
+                }
+                builder
+            })
+            .insert("lines_analyzed", self.lines_analyzed as u64)
+            .insert("score", self.get_score())
+            .insert("truncated_findings", self.truncated_findings as u64)
+            .insert_array("rule_matches", |builder| {
+                let mut builder = builder;
+                for &(ref id, count) in &self.rule_match_counts {
+                    builder = builder.push_object(|builder| {
+                        builder.insert("rule", id.as_str()).insert("count", count as u64)
+                    });
+                }
+                builder
+            })
+            .insert_array("rule_coverage", |builder| {
+                let mut builder = builder;
+                for &(ref id, ref skip_reason) in &self.rule_coverage {
+                    builder = builder.push_object(|builder| {
+                        let builder = builder.insert("rule", id.as_str())
+                            .insert("active", skip_reason.is_none());
+                        match *skip_reason {
+                            Some(ref reason) => builder.insert("skip_reason", reason.as_str()),
+                            None => builder,
+                        }
+                    });
+                }
+                builder
+            })
+            .insert_object("density", |builder| {
+                builder.insert("warnings", self.get_density(Criticity::Warning))
+                    .insert("low", self.get_density(Criticity::Low))
+                    .insert("medium", self.get_density(Criticity::Medium))
+                    .insert("high", self.get_density(Criticity::High))
+                    .insert("critical", self.get_density(Criticity::Critical))
+            })
+            .build()
     }
 
     fn generate_html_report(&self, config: &Config) -> Result<()> {
@@ -257,13 +1117,26 @@ impl Results {
             println!("The report file has been created. Now it's time to fill it.")
         }
 
+        try!(self.write_html_report(&mut f));
+
+        // Copying JS and CSS files
+        try!(copy_folder(config.get_results_template(),
+                         &format!("{}/{}", config.get_results_folder(), config.get_app_id())));
+
+        try!(self.generate_code_html_files(config));
+
+        Ok(())
+    }
+
+    fn write_html_report<W: Write>(&self, f: &mut W) -> Result<()> {
         let now = Local::now();
+        let lang = self.lang;
 
         // Header
         try!(f.write_all(b"<!DOCTYPE html>"));
-        try!(f.write_all(b"<html lang=\"en\">"));
+        try!(f.write_all(&format!("<html lang=\"{}\">", lang).into_bytes()));
         try!(f.write_all(b"<head>"));
-        try!(f.write_all(b"<title>Vulnerability report</title>"));
+        try!(f.write_all(&format!("<title>{}</title>", lang.get("report_title")).into_bytes()));
         try!(f.write_all(b"<meta charset=\"UTF-8\">"));
         try!(f.write_all(b"<link rel=\"stylesheet\" href=\"css/style.css\">"));
         try!(f.write_all(b"<link rel=\"stylesheet\" href=\"css/androidstudio.css\">"));
@@ -273,53 +1146,62 @@ impl Results {
         try!(f.write_all(b"<a href=\"http://superanalyzer.rocks\" \
                                 title=\"S.U.P.E.R. Android Analyzer\">\
                             <img src=\"img/logo.png\" alt=\"S.U.P.E.R. Android Analyzer\"></a>"));
-        try!(f.write_all(b"<h1 id=\"title\">S.U.P.E.R. Android Analyzer \
-                            Report</h1>"));
-        try!(f.write_all(&format!("<p>This is the vulnerability report for the android \
-                                   application <em>{}</em>. Report generated on {}.</p>",
+        try!(f.write_all(&format!("<h1 id=\"title\">{}</h1>", lang.get("report_header"))
+            .into_bytes()));
+        try!(f.write_all(&format!("<p>{} <em>{}</em>. {} {}.</p>",
+                                  lang.get("report_intro"),
                                   self.app_package,
+                                  lang.get("report_date"),
                                   now.to_rfc2822())
             .into_bytes()));
 
         // Application data
-        try!(f.write_all(b"<h2>Application data:</h2>"));
+        try!(f.write_all(&format!("<h2>{}:</h2>", lang.get("application_data")).into_bytes()));
         try!(f.write_all(b"<ul>"));
         if !self.app_label.is_empty() {
-            try!(f.write_all(&format!("<li><strong>Label:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("label"),
                                       self.app_label.as_str())
                 .into_bytes()));
         }
         if !self.app_description.is_empty() {
-            try!(f.write_all(&format!("<li><strong>Description:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("description"),
                                       self.app_description.as_str())
                 .into_bytes()));
         }
         if !self.app_package.is_empty() {
-            try!(f.write_all(&format!("<li><strong>Package:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("package"),
                                       self.app_package.as_str())
                 .into_bytes()));
         }
         if !self.app_version.is_empty() {
-            try!(f.write_all(&format!("<li><strong>Version:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("version"),
                                       self.app_version.as_str())
                 .into_bytes()));
         }
         if self.app_version_num > 0 {
-            try!(f.write_all(&format!("<li><strong>Version number:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("version_number"),
                                       self.app_version_num)
                 .into_bytes()));
         }
         if self.app_min_sdk > 0 {
-            try!(f.write_all(&format!("<li><strong>Minimum SDK version:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("min_sdk"),
                                       self.app_min_sdk)
                 .into_bytes()));
         }
         if self.app_target_sdk.is_some() {
-            try!(f.write_all(&format!("<li><strong>Target SDK:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      lang.get("target_sdk"),
                                       self.app_target_sdk.unwrap())
                 .into_bytes()));
         }
-        try!(f.write_all(b"<li><strong>Fingerprints:</strong><ul>"));
+        try!(f.write_all(&format!("<li><strong>{}:</strong><ul>", lang.get("fingerprints"))
+            .into_bytes()));
         try!(f.write_all(&format!("<li>MD5: {}</li>", self.app_fingerprint.get_md5().to_hex())
             .into_bytes()));
         try!(f.write_all(&format!("<li>SHA-1: {}</li>",
@@ -330,77 +1212,97 @@ impl Results {
             .into_bytes()));
         try!(f.write_all(b"</ul></li>"));
 
-        try!(f.write_all(b"<li><a href=\"src/index.html\" \
-                        title=\"Source code\">Check source code</a></li>"));
+        try!(f.write_all(&format!("<li><a href=\"src/index.html\" title=\"{}\">{}</a></li>",
+                                  lang.get("source_code"),
+                                  lang.get("check_source_code"))
+            .into_bytes()));
         try!(f.write_all(b"</ul>"));
 
         // Vulnerability count
         let total_vuln = self.low.len() + self.medium.len() + self.high.len() + self.critical.len();
-        try!(f.write_all(&format!("<h3>Total vulnerabilities found: {}</h3>", total_vuln)
+        try!(f.write_all(&format!("<h3>{}: {}</h3>", lang.get("total_vulnerabilities"), total_vuln)
             .into_bytes()));
         try!(f.write_all(b"<ul>"));
-        if self.critical.len() == 0 {
-            try!(f.write_all(b"<li>Critical: 0</li>"));
-        } else {
-            try!(f.write_all(&format!("<li>Critical: <span class=\"critical\">{}</span> <a \
-                                       href=\"#critical\" title=\"Critical\">⇒</a></li>",
-                                      self.critical.len())
-                .into_bytes()));
-        }
-        if self.high.len() == 0 {
-            try!(f.write_all(b"<li>High: 0</li>"));
-        } else {
-            try!(f.write_all(&format!("<li>High: <span class=\"high\">{}</span> <a \
-                                       href=\"#high\" title=\"High\">⇒</a></li>",
-                                      self.high.len())
-                .into_bytes()));
-        }
-        if self.medium.len() == 0 {
-            try!(f.write_all(b"<li>Medium: 0</li>"));
-        } else {
-            try!(f.write_all(&format!("<li>Medium: <span class=\"medium\">{}</span> <a \
-                                       href=\"#medium\" title=\"Medium\">⇒</a></li>",
-                                      self.medium.len())
-                .into_bytes()));
+        for &(set, id) in &[(&self.critical, "critical"),
+                            (&self.high, "high"),
+                            (&self.medium, "medium"),
+                            (&self.low, "low"),
+                            (&self.warnings, "warnings")] {
+            if set.len() == 0 {
+                try!(f.write_all(&format!("<li>{}: 0</li>", lang.get(id)).into_bytes()));
+            } else {
+                try!(f.write_all(&format!("<li>{0}: <span class=\"{1}\">{2}</span> <a \
+                                           href=\"#{1}\" title=\"{0}\">⇒</a></li>",
+                                          lang.get(id),
+                                          id,
+                                          set.len())
+                    .into_bytes()));
+            }
         }
-        if self.low.len() == 0 {
-            try!(f.write_all(b"<li>Low: 0</li>"));
-        } else {
-            try!(f.write_all(&format!("<li>Low: <span class=\"low\">{}</span> <a href=\"#low\" \
-                                       title=\"Low\">⇒</a></li>",
-                                      self.low.len())
+        try!(f.write_all(b"</ul>"));
+        try!(f.write_all(&format!("<h3>{}: {:.2}</h3>",
+                                  lang.get("application_score"),
+                                  self.get_score())
+            .into_bytes()));
+
+        try!(f.write_all(&format!("<h3>{} ({} {})</h3>",
+                                  lang.get("findings_density"),
+                                  self.lines_analyzed,
+                                  lang.get("lines_analyzed"))
+            .into_bytes()));
+        try!(f.write_all(&format!("<ul><li>{}: {:.2}</li><li>{}: {:.2}</li><li>{}: \
+                                   {:.2}</li><li>{}: {:.2}</li><li>{}: {:.2}</li></ul>",
+                                  lang.get("critical"),
+                                  self.get_density(Criticity::Critical),
+                                  lang.get("high"),
+                                  self.get_density(Criticity::High),
+                                  lang.get("medium"),
+                                  self.get_density(Criticity::Medium),
+                                  lang.get("low"),
+                                  self.get_density(Criticity::Low),
+                                  lang.get("warnings"),
+                                  self.get_density(Criticity::Warning))
+            .into_bytes()));
+
+        if self.truncated_findings > 0 {
+            try!(f.write_all(&format!("<h3>{}: {}</h3>",
+                                      lang.get("truncated_findings"),
+                                      self.truncated_findings)
                 .into_bytes()));
         }
-        if self.warnings.len() == 0 {
-            try!(f.write_all(b"<li>Warnings: 0</li>"));
-        } else {
-            try!(f.write_all(&format!("<li>Warnings: <span class=\"warnings\">{}</span> <a \
-                                       href=\"#warnings\" title=\"Warnings\">⇒</a></li>",
-                                      self.warnings.len())
+
+        if !self.timed_out_files.is_empty() {
+            try!(f.write_all(&format!("<h3>{}: {}</h3>",
+                                      lang.get("timed_out_files"),
+                                      self.timed_out_files.len())
                 .into_bytes()));
+            try!(f.write_all(b"<ul>"));
+            for file in &self.timed_out_files {
+                try!(f.write_all(&format!("<li>{}</li>", file).into_bytes()));
+            }
+            try!(f.write_all(b"</ul>"));
         }
-        try!(f.write_all(b"</ul>"));
 
-        try!(f.write_all(b"<h2>Vulnerabilities:</h2>"));
+        try!(f.write_all(&format!("<h2>{}:</h2>", lang.get("vulnerabilities")).into_bytes()));
 
         if self.critical.len() > 0 {
-            try!(self.print_html_vuln_set(&mut f, &self.critical, Criticity::Critical))
+            try!(self.print_html_vuln_set(f, &self.critical, Criticity::Critical))
         }
 
         if self.high.len() > 0 {
-            try!(self.print_html_vuln_set(&mut f, &self.high, Criticity::High))
+            try!(self.print_html_vuln_set(f, &self.high, Criticity::High))
         }
 
         if self.medium.len() > 0 {
-            try!(self.print_html_vuln_set(&mut f, &self.medium, Criticity::Medium))
+            try!(self.print_html_vuln_set(f, &self.medium, Criticity::Medium))
         }
 
         if self.low.len() > 0 {
-            try!(self.print_html_vuln_set(&mut f, &self.low, Criticity::Low))
+            try!(self.print_html_vuln_set(f, &self.low, Criticity::Low))
         }
 
         if self.warnings.len() > 0 {
-            try!(self.print_html_vuln_set(&mut f, &self.warnings, Criticity::Warning))
+            try!(self.print_html_vuln_set(f, &self.warnings, Criticity::Warning))
         }
         try!(f.write_all(b"</section>"));
 
@@ -432,70 +1334,95 @@ impl Results {
         try!(f.write_all(b"</body>"));
         try!(f.write_all(b"</html>"));
 
-        // Copying JS and CSS files
-        try!(copy_folder(config.get_results_template(),
-                         &format!("{}/{}", config.get_results_folder(), config.get_app_id())));
-
-        try!(self.generate_code_html_files(config));
-
         Ok(())
     }
 
-    fn print_html_vuln_set(&self,
-                           f: &mut File,
-                           set: &BTreeSet<Vulnerability>,
-                           criticity: Criticity)
-                           -> Result<()> {
+    fn print_html_vuln_set<W: Write>(&self,
+                                     f: &mut W,
+                                     set: &BTreeSet<Vulnerability>,
+                                     criticity: Criticity)
+                                     -> Result<()> {
         let criticity_str = format!("{:?}", criticity);
         if criticity == Criticity::Warning {
-            try!(f.write_all(&String::from("<h3 id=\"warnings\">Warnings: <a href=\"#title\" \
-                                            title=\"Top\">⇮</a></h3>")
+            try!(f.write_all(&format!("<h3 id=\"warnings\">{}: <a href=\"#title\" \
+                                       title=\"{}\">⇮</a></h3>",
+                                      self.lang.get("warnings"),
+                                      self.lang.get("top"))
                 .into_bytes()));
 
         } else {
-            try!(f.write_all(&format!("<h3 id=\"{}\">{} criticity vulnerabilities: <a \
-                                       href=\"#title\" title=\"Top\">⇮</a></h3>",
-                                      criticity_str.to_lowercase(),
-                                      criticity_str)
+            let id = criticity_str.to_lowercase();
+            try!(f.write_all(&format!("<h3 id=\"{}\">{}: <a href=\"#title\" \
+                                       title=\"{}\">⇮</a></h3>",
+                                      id,
+                                      self.lang.get(&format!("{}_vulnerabilities", id)),
+                                      self.lang.get("top"))
                 .into_bytes()));
         }
 
         for (i, vuln) in set.iter().enumerate() {
             try!(f.write_all(b"<section class=\"vulnerability\">"));
-            try!(f.write_all(&format!("<h4>{}{:03}: <a href=\"#\" title=\"Display \
-                                       vulnerability\" class=\"show\">+</a><a href=\"#\" \
-                                       style=\"display: none\" class=\"collapse\" \
-                                       title=\"Collapse vulnerability\">-</a></h4>",
+            try!(f.write_all(&format!("<h4>{}{:03}: <a href=\"#\" title=\"{}\" \
+                                       class=\"show\">+</a><a href=\"#\" style=\"display: \
+                                       none\" class=\"collapse\" title=\"{}\">-</a></h4>",
                                       criticity_str.chars().nth(0).unwrap(),
-                                      i + 1)
+                                      i + 1,
+                                      self.lang.get("display_vulnerability"),
+                                      self.lang.get("collapse_vulnerability"))
                 .into_bytes()));
             try!(f.write_all(b"<ul>"));
-            try!(f.write_all(&format!("<li><strong>Label:</strong> {}</li>", vuln.get_name())
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      self.lang.get("label"),
+                                      vuln.get_name())
                 .into_bytes()));
+            if vuln.get_message() != vuln.get_description() {
+                try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                          self.lang.get("message"),
+                                          Results::html_escape(vuln.get_message()))
+                    .into_bytes()));
+            }
             try!(f.write_all(b"<div style=\"display: none\">"));
-            try!(f.write_all(&format!("<li><strong>Description:</strong> {}</li>",
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      self.lang.get("description"),
                                       vuln.get_description())
                 .into_bytes()));
+            try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                      self.lang.get("confidence"),
+                                      self.lang
+                                          .get(&vuln.get_confidence().to_string())
+                                          .to_lowercase())
+                .into_bytes()));
             if let Some(file) = vuln.get_file() {
-                try!(f.write_all(&format!("<li><strong>File:</strong> <a \
-                                           href=\"src/{0}.html\">{0}</a></li>",
-                                          file.display())
-                    .into_bytes()));
+                // There is only a code page for the files reported relative to the source.
+                if file.is_relative() {
+                    try!(f.write_all(&format!("<li><strong>{0}:</strong> <a \
+                                               href=\"src/{1}.html\">{1}</a></li>",
+                                              self.lang.get("source_file"),
+                                              file.display())
+                        .into_bytes()));
+                } else {
+                    try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                              self.lang.get("source_file"),
+                                              file.display())
+                        .into_bytes()));
+                }
             }
             if let Some(code) = vuln.get_code() {
                 if vuln.get_start_line().unwrap() != vuln.get_end_line().unwrap() {
-                    try!(f.write_all(&format!("<li><strong>Lines:</strong> {}-{}</li>",
-                                              vuln.get_start_line().unwrap() + 1,
-                                              vuln.get_end_line().unwrap() + 1)
+                    try!(f.write_all(&format!("<li><strong>{}:</strong> {}-{}</li>",
+                                              self.lang.get("lines"),
+                                              vuln.get_start_line().unwrap(),
+                                              vuln.get_end_line().unwrap())
                         .into_bytes()));
                 } else {
-                    try!(f.write_all(&format!("<li><strong>Line:</strong> {}</li>",
-                                              vuln.get_start_line().unwrap() + 1)
+                    try!(f.write_all(&format!("<li><strong>{}:</strong> {}</li>",
+                                              self.lang.get("line"),
+                                              vuln.get_start_line().unwrap())
                         .into_bytes()));
                 }
 
                 let start_line = if vuln.get_start_line().unwrap() < 5 {
-                    0
+                    1
                 } else {
                     vuln.get_start_line().unwrap() - 4
                 };
@@ -504,16 +1431,17 @@ impl Results {
                 for (i, _line) in code.lines().enumerate() {
                     if i + start_line >= vuln.get_start_line().unwrap() &&
                        i + start_line <= vuln.get_end_line().unwrap() {
-                        lines.push_str(format!("-&gt;<em>{}</em><br>", i + start_line+1).as_str());
+                        lines.push_str(format!("-&gt;<em>{}</em><br>", i + start_line).as_str());
                     } else {
-                        lines.push_str(format!("{}<br>", i + start_line + 1).as_str());
+                        lines.push_str(format!("{}<br>", i + start_line).as_str());
                     }
                 }
                 let lang = vuln.get_file().unwrap().extension().unwrap().to_string_lossy();
-                try!(f.write_all(&format!("<li><p><strong>Affected code:</strong></p><div><div \
+                try!(f.write_all(&format!("<li><p><strong>{}:</strong></p><div><div \
                                            class=\"line_numbers\">{}</div><div \
                                            class=\"code\"><pre><code \
                                            class=\"{}\">{}</code></pre></div></li>",
+                                          self.lang.get("affected_code"),
                                           lines,
                                           lang,
                                           Results::html_escape(code))
@@ -564,9 +1492,8 @@ impl Results {
            path.as_ref() == Path::new("smali") {
             return Ok(0);
         }
-        let dir_iter = try!(fs::read_dir(&format!("{}/{}/{}",
-                                                  config.get_dist_folder(),
-                                                  config.get_app_id(),
+        let dir_iter = try!(fs::read_dir(&format!("{}/{}",
+                                                  config.get_analysis_folder(),
                                                   path.as_ref().display())));
 
         try!(fs::create_dir_all(&format!("{}/{}/src/{}",
@@ -579,9 +1506,8 @@ impl Results {
             let f = match f {
                 Ok(f) => f,
                 Err(e) => {
-                    print_warning(format!("There was an error reading the directory {}/{}/{}: {}",
-                                          config.get_dist_folder(),
-                                          config.get_app_id(),
+                    print_warning(format!("There was an error reading the directory {}/{}: {}",
+                                          config.get_analysis_folder(),
                                           path.as_ref().display(),
                                           e),
                                   config.is_verbose());
@@ -592,8 +1518,7 @@ impl Results {
             match f.path().extension() {
                 Some(e) => {
                     if e.to_string_lossy() == "xml" || e.to_string_lossy() == "java" {
-                        let prefix =
-                            format!("{}/{}/", config.get_dist_folder(), config.get_app_id());
+                        let prefix = config.get_analysis_folder();
                         try!(self.generate_code_html_for(f.path().strip_prefix(&prefix).unwrap(),
                                                          config));
                         count += 1;
@@ -601,8 +1526,7 @@ impl Results {
                 }
                 None => {
                     if f.path().is_dir() {
-                        let prefix =
-                            format!("{}/{}/", config.get_dist_folder(), config.get_app_id());
+                        let prefix = config.get_analysis_folder();
 
                         if f.path().strip_prefix(&prefix).unwrap() != Path::new("original") {
                             let f_count = try!(self.generate_code_html_folder(f.path()
@@ -712,9 +1636,8 @@ impl Results {
     }
 
     fn generate_code_html_for<P: AsRef<Path>>(&self, path: P, config: &Config) -> Result<()> {
-        let mut f_in = try!(File::open(format!("{}/{}/{}",
-                                               config.get_dist_folder(),
-                                               config.get_app_id(),
+        let mut f_in = try!(File::open(format!("{}/{}",
+                                               config.get_analysis_folder(),
                                                path.as_ref().display())));
         let mut f_out = try!(File::create(format!("{}/{}/src/{}.html",
                                                   config.get_results_folder(),
@@ -777,3 +1700,717 @@ impl Results {
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::collections::BTreeMap;
+    use std::fs::File;
+    use std::io::Read;
+    use std::iter::repeat;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use flate2::read::GzDecoder;
+    use serde_json;
+    use serde_json::value::Value;
+
+    use {Criticity, Confidence, Config};
+    use super::{Results, Vulnerability, VulnerabilityBuilder, Benchmark, ReportFormat, Lang,
+                REPORT_FORMATS};
+
+    fn write_test_report(results: &Results, format: ReportFormat) -> String {
+        let mut report = Vec::new();
+        results.write_report(format, &mut report).unwrap();
+        String::from_utf8(report).unwrap()
+    }
+
+    #[test]
+    fn it_json_gz_report() {
+        let mut results: Results = Default::default();
+        results.set_app_package("com.example.gz");
+        results.add_vulnerability(Vulnerability::new(Criticity::High,
+                                                     "Test vulnerability",
+                                                     "A vulnerability to test the report",
+                                                     Some("MainActivity.java"),
+                                                     Some(3),
+                                                     Some(4),
+                                                     None));
+        results.add_timed_out_file("Huge.java");
+        results.add_known_good_file("okhttp3/OkHttpClient.java");
+
+        let path = "test_results_report.json.gz";
+        results.generate_json_gz_report(path).unwrap();
+
+        let mut decoder = GzDecoder::new(File::open(path).unwrap()).unwrap();
+        let mut json = String::new();
+        decoder.read_to_string(&mut json).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(json, format!("{:?}", results.get_json_report()));
+        assert!(json.contains("\"package\":\"com.example.gz\""));
+        assert!(json.contains("\"name\":\"Test vulnerability\""));
+        assert!(json.contains("\"timed_out_files\":[\"Huge.java\"]"));
+        assert!(json.contains("\"known_good_files\":[\"okhttp3/OkHttpClient.java\"]"));
+    }
+
+    #[test]
+    fn it_output_dir() {
+        let mut results: Results = Default::default();
+        results.set_app_package("com.example.dir");
+        results.add_vulnerability(Vulnerability::new(Criticity::Medium,
+                                                     "Test vulnerability",
+                                                     "A vulnerability to test the reports",
+                                                     Some("MainActivity.java"),
+                                                     Some(3),
+                                                     Some(3),
+                                                     None));
+
+        let folder = "test_output_dir";
+        let dir = format!("{}/reports", folder);
+        results.write_reports_to_dir(&dir, &[ReportFormat::Json, ReportFormat::Sarif]).unwrap();
+        let read_report = |name: &str| {
+            let mut report = String::new();
+            File::open(format!("{}/{}", dir, name)).unwrap().read_to_string(&mut report).unwrap();
+            report
+        };
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|f| f.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["report.json", "report.sarif"]);
+        assert_eq!(read_report("report.json"),
+                   write_test_report(&results, ReportFormat::Json));
+        assert_eq!(read_report("report.sarif"),
+                   write_test_report(&results, ReportFormat::Sarif));
+
+        // The existing reports are overwritten
+        results.write_reports_to_dir(&dir, &REPORT_FORMATS).unwrap();
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|f| f.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(read_report("report.csv"), write_test_report(&results, ReportFormat::Csv));
+        fs::remove_dir_all(folder).unwrap();
+        assert_eq!(names,
+                   vec!["report.csv", "report.html", "report.json", "report.sarif", "report.txt"]);
+        // Without an output folder, only one format can be written
+        let mut config: Config = Default::default();
+        config.set_report_formats(vec![ReportFormat::Json, ReportFormat::Sarif]);
+        assert!(results.generate_report(&config).is_err());
+    }
+
+    #[test]
+    fn it_report_lang() {
+        let mut results: Results = Default::default();
+        results.set_app_package("com.example.lang");
+        results.set_app_label("Lang");
+        results.set_app_description("Application to test the languages");
+        results.set_app_version("1.0");
+        results.set_app_version_num(1);
+        results.set_app_min_sdk(16);
+        results.set_app_target_sdk(29);
+        results.add_timed_out_file("Huge.java");
+        for &(file, end_line) in &[("A.java", 2), ("B.java", 1), ("C.java", 1)] {
+            results.add_vulnerability(VulnerabilityBuilder::new(Criticity::High,
+                                                                "Test vulnerability",
+                                                                "A vulnerability to test the \
+                                                                 reports")
+                .message("Test message")
+                .file(file)
+                .lines(1, end_line)
+                .code("int a = 1;\nint b = 2;")
+                .build());
+        }
+        results.truncate_findings(2);
+
+        let html = write_test_report(&results, ReportFormat::Html);
+        let english = html.clone();
+        assert!(html.contains("<html lang=\"en\">"));
+        assert!(html.contains("<h2>Vulnerabilities:</h2>"));
+        assert!(html.contains(">High criticity vulnerabilities: <a"));
+        assert!(results.get_criticity_summary(false).contains("high     "));
+        assert!(results.get_criticity_summary(false).contains("2 in 2 files"));
+
+        results.lang = Lang::Es;
+        let html = write_test_report(&results, ReportFormat::Html);
+        assert!(html.contains("<html lang=\"es\">"));
+        assert!(html.contains("<h2>Vulnerabilidades:</h2>"));
+        assert!(html.contains(">Vulnerabilidades de criticidad alta: <a"));
+        assert!(!html.contains("<h2>Vulnerabilities:</h2>"));
+        assert!(html.contains("<em>com.example.lang</em>"));
+        // The findings are not translated
+        assert!(html.contains("Test vulnerability"));
+        assert!(results.get_criticity_summary(false).contains("alta     "));
+        assert!(results.get_criticity_summary(false).contains("2 en 2 archivos"));
+        assert!(html.contains("<li><strong>Etiqueta:</strong> Lang</li>"));
+        assert!(html.contains("<li><strong>Confianza:</strong> alta</li>"));
+        assert!(html.contains("<h3>Hallazgos no informados por haber alcanzado el máximo: \
+                               1</h3>"));
+
+        // Every label, header and title of the report is translated
+        for &(start, end) in &[("<strong>", "</strong>"),
+                               ("<h2>", "</h2>"),
+                               ("<h3", "</h3>"),
+                               (" title=\"", "\"")] {
+            let english_texts = get_texts(&english, start, end);
+            let texts = get_texts(&html, start, end);
+            assert!(!texts.is_empty());
+            assert_eq!(texts.len(), english_texts.len());
+            for (english_text, text) in english_texts.iter().zip(texts) {
+                assert!(*english_text == "S.U.P.E.R. Android Analyzer" || *english_text != text,
+                        "{} is not translated",
+                        english_text);
+            }
+        }
+    }
+
+    /// Gets the texts of the report between each of the given start and end marks.
+    fn get_texts<'r>(report: &'r str, start: &str, end: &str) -> Vec<&'r str> {
+        report.split(start).skip(1).map(|t| &t[..t.find(end).unwrap()]).collect()
+    }
+
+    #[test]
+    fn it_json_rule_matches() {
+        let mut results: Results = Default::default();
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"rule_matches\":[]"));
+
+        results.add_rule_match_count("url_disclosure", 3);
+        results.add_rule_match_count("random", 1);
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"rule_matches\":[{\"count\":3,\"rule\":\"url_disclosure\"},\
+                               {\"count\":1,\"rule\":\"random\"}]"));
+    }
+
+    #[test]
+    fn it_json_rule_coverage() {
+        let mut results: Results = Default::default();
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"rule_coverage\":[]"));
+
+        results.add_rule_coverage("url_disclosure", None);
+        results.add_rule_coverage("sms", Some("the application does not need the SEND_SMS \
+                                               permission"));
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"rule_coverage\":[{\"active\":true,\"rule\":\"url_disclosure\"},\
+                               {\"active\":false,\"rule\":\"sms\",\"skip_reason\":\"the \
+                               application does not need the SEND_SMS permission\"}]"));
+    }
+
+    #[test]
+    fn it_json_benchmarks() {
+        let mut results: Results = Default::default();
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"benchmarks\":[]"));
+
+        results.add_benchmark(Benchmark::new("Rule loading", Duration::new(1, 500_000_000)));
+        results.add_benchmark(Benchmark::new("Code analysis", Duration::from_millis(20)));
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"benchmarks\":[{\"duration_ms\":1500,\"label\":\"Rule \
+                               loading\"},{\"duration_ms\":20,\"label\":\"Code analysis\"}]"));
+
+        let sarif: Value =
+            serde_json::from_str(&write_test_report(&results, ReportFormat::Sarif)).unwrap();
+        let run = &sarif.find("runs").and_then(|r| r.as_array()).unwrap()[0];
+        let benchmarks = run.find_path(&["properties", "benchmarks"])
+            .and_then(|b| b.as_array())
+            .unwrap();
+        assert_eq!(benchmarks.len(), 2);
+        assert_eq!(benchmarks[1].find("duration_ms").and_then(|d| d.as_u64()), Some(20));
+
+        let csv = write_test_report(&results, ReportFormat::Csv);
+        let lines: Vec<_> = csv.lines().skip(1).collect();
+        assert_eq!(lines,
+                   vec!["benchmark,,,,Rule loading,,,,,,1500 ms",
+                        "benchmark,,,,Code analysis,,,,,,20 ms"]);
+    }
+
+    #[test]
+    fn it_density() {
+        let mut results: Results = Default::default();
+        assert_eq!(results.get_density(Criticity::High), 0_f64);
+
+        for line in 0..3 {
+            results.add_vulnerability(Vulnerability::new(Criticity::High,
+                                                         "Test vulnerability",
+                                                         "A vulnerability to test the density",
+                                                         Some("MainActivity.java"),
+                                                         Some(line),
+                                                         Some(line),
+                                                         None));
+        }
+        results.add_vulnerability(Vulnerability::new(Criticity::Low,
+                                                     "Test warning",
+                                                     "A vulnerability to test the density",
+                                                     Some("MainActivity.java"),
+                                                     Some(10),
+                                                     Some(10),
+                                                     None));
+        assert_eq!(results.get_density(Criticity::High), 0_f64);
+
+        results.set_lines_analyzed(1500);
+        assert_eq!(results.get_density(Criticity::High), 2_f64);
+        assert_eq!(results.get_density(Criticity::Low), 1_f64 / 1.5);
+        assert_eq!(results.get_density(Criticity::Critical), 0_f64);
+
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("\"lines_analyzed\":1500"));
+        assert!(json.contains("\"high\":2.0"));
+    }
+
+    #[test]
+    fn it_compare() {
+        let code = "class A {\n    double r = Math.random();\n}\n";
+        let moved = "import java.util.List;\n\nclass A {\n    double r = Math.random();\n}\n";
+        let vuln = |name: &str, file: &str, line: usize, code: &str| {
+            let mut vuln = Vulnerability::new(Criticity::Low,
+                                              name,
+                                              "A vulnerability to test the comparison",
+                                              Some(file),
+                                              Some(line),
+                                              Some(line),
+                                              Some(String::from(code)));
+            vuln.set_rule_id(name);
+            vuln
+        };
+
+        let mut previous: Results = Default::default();
+        previous.add_vulnerability(vuln("random", "A.java", 2, code));
+        previous.add_vulnerability(vuln("url_disclosure", "B.java", 2, code));
+        let mut report = Vec::new();
+        previous.write_report(ReportFormat::Json, &mut report).unwrap();
+
+        // The random finding only moved two lines down, so it must be the same one.
+        let mut results: Results = Default::default();
+        let existing = vuln("random", "A.java", 4, moved);
+        let new = vuln("random", "C.java", 2, code);
+        results.add_vulnerability(existing.clone());
+        results.add_vulnerability(new.clone());
+
+        let comparison = results.compare(report.as_slice()).unwrap();
+        assert_eq!(comparison.get_new().iter().collect::<Vec<_>>(),
+                   vec![&new.get_fingerprint()]);
+        assert_eq!(comparison.get_existing().iter().collect::<Vec<_>>(),
+                   vec![&existing.get_fingerprint()]);
+        assert_eq!(comparison.get_fixed().iter().collect::<Vec<_>>(),
+                   vec![&vuln("url_disclosure", "B.java", 2, code).get_fingerprint()]);
+
+        results.remove_existing(&comparison);
+        let json = format!("{:?}", results.get_json_report());
+        assert!(json.contains("C.java"));
+        assert!(!json.contains("A.java"));
+
+        assert!(results.compare(&b"{\"low\":[{\"name\":\"random\"}]}"[..]).is_err());
+    }
+
+    #[test]
+    fn it_baseline() {
+        let findings = [(Criticity::Critical, "sql", "com/example/Db.java", 40),
+                        (Criticity::Low, "log", "com/example/Main.java", 7),
+                        (Criticity::Low, "log", "com/example/Utils.java", 12)];
+        let results = |findings: &[(Criticity, &str, &str, usize)]| {
+            let mut results: Results = Default::default();
+            for &(criticity, rule, file, line) in findings {
+                let mut vuln = Vulnerability::new(criticity,
+                                                  rule,
+                                                  "A vulnerability to test the baseline",
+                                                  Some(file),
+                                                  Some(line),
+                                                  Some(line),
+                                                  Some(format!("code of line {}", line)));
+                vuln.set_rule_id(rule);
+                results.add_vulnerability(vuln);
+            }
+            results
+        };
+
+        // The baseline does not depend on the order of the findings
+        let mut baseline = Vec::new();
+        results(&findings).write_baseline(&mut baseline).unwrap();
+        let mut reversed = Vec::new();
+        let reversed_findings: Vec<_> = findings.iter().rev().cloned().collect();
+        results(&reversed_findings).write_baseline(&mut reversed).unwrap();
+        assert_eq!(baseline, reversed);
+
+        let json: Value = serde_json::from_slice(&baseline).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].find("rule_id").and_then(|r| r.as_str()), Some("log"));
+        assert_eq!(entries[0].find("file").and_then(|f| f.as_str()),
+                   Some("com/example/Main.java"));
+        assert_eq!(entries[0].find("line").and_then(|l| l.as_u64()), Some(7));
+        assert!(entries[0].find("fingerprint").and_then(|f| f.as_str()).is_some());
+
+        // The same findings are suppressed completely
+        let mut same = results(&findings);
+        assert_eq!(same.suppress_baseline(baseline.as_slice()).unwrap(), 3);
+        assert_eq!(same.get_total_findings(), 0);
+
+        // New findings are kept
+        let mut new = results(&[(Criticity::High, "sql", "com/example/Api.java", 3)]);
+        assert_eq!(new.suppress_baseline(baseline.as_slice()).unwrap(), 0);
+        assert_eq!(new.get_total_findings(), 1);
+
+        assert!(new.suppress_baseline(&b"[{\"rule_id\": \"sql\"}]"[..]).is_err());
+
+        // The maximum number of findings keeps the most critical ones
+        let mut truncated = results(&findings);
+        truncated.truncate_findings(1);
+        assert_eq!(truncated.get_total_findings(), 1);
+        assert_eq!(truncated.get_truncated_findings(), 2);
+        assert_eq!(truncated.get_highest_criticity(), Some(Criticity::Critical));
+
+        // The findings suppressed by the baseline do not count for the maximum
+        let mut baseline = Vec::new();
+        results(&findings[1..]).write_baseline(&mut baseline).unwrap();
+        let mut suppressed = results(&findings);
+        assert_eq!(suppressed.suppress_baseline(baseline.as_slice()).unwrap(), 2);
+        suppressed.truncate_findings(1);
+        assert_eq!(suppressed.get_total_findings(), 1);
+        assert_eq!(suppressed.get_truncated_findings(), 0);
+    }
+
+    #[test]
+    fn it_score() {
+        let mut results: Results = Default::default();
+        results.score_weights = Config::default().get_score_weights().clone();
+        assert_eq!(results.get_score(), 0_f64);
+
+        let criticities = [(Criticity::Critical, 1),
+                           (Criticity::High, 2),
+                           (Criticity::Medium, 3),
+                           (Criticity::Low, 4),
+                           (Criticity::Warning, 5)];
+        for &(criticity, count) in &criticities {
+            for line in 0..count {
+                results.add_vulnerability(Vulnerability::new(criticity,
+                                                             "Test vulnerability",
+                                                             "A vulnerability to test the score",
+                                                             Some("MainActivity.java"),
+                                                             Some(line),
+                                                             Some(line),
+                                                             None));
+            }
+        }
+        // 1 × 10 + 2 × 5 + 3 × 2 + 4 × 1 + 5 × 0.5
+        assert_eq!(results.get_score(), 32.5);
+        assert!(format!("{:?}", results.get_json_report()).contains("\"score\":32.5"));
+
+        let mut weights = BTreeMap::new();
+        weights.insert(Criticity::Critical, 100_f64);
+        results.score_weights = weights;
+        assert_eq!(results.get_score(), 100_f64);
+    }
+
+    #[test]
+    fn it_exit_code() {
+        let mut config: Config = Default::default();
+        config.set_exit_code(Criticity::Critical, 2);
+        config.set_exit_code(Criticity::High, 3);
+
+        let mut results: Results = Default::default();
+        assert_eq!(results.get_highest_criticity(), None);
+
+        for &criticity in &[Criticity::Low, Criticity::High, Criticity::Warning] {
+            results.add_vulnerability(Vulnerability::new(criticity,
+                                                         "Test vulnerability",
+                                                         "A vulnerability to test the exit code",
+                                                         Some("MainActivity.java"),
+                                                         Some(1),
+                                                         Some(1),
+                                                         None));
+        }
+        let highest = results.get_highest_criticity();
+        assert_eq!(highest, Some(Criticity::High));
+        assert_eq!(highest.and_then(|c| config.get_exit_code(c)), Some(3));
+        assert_eq!(config.get_exit_code(Criticity::Medium), None);
+    }
+
+    #[test]
+    fn it_criticity_summary() {
+        let mut results: Results = Default::default();
+        assert_eq!(results.get_criticity_summary(false),
+                   "critical  0\nhigh      0\nmedium    0\nlow       0\nwarning   0\n");
+
+        let counts = [(Criticity::Critical, 1), (Criticity::Medium, 4), (Criticity::Low, 8)];
+        for &(criticity, count) in &counts {
+            for line in 0..count {
+                results.add_vulnerability(Vulnerability::new(criticity,
+                                                             "Test vulnerability",
+                                                             "A vulnerability to test the summary",
+                                                             Some("MainActivity.java"),
+                                                             Some(line + 1),
+                                                             Some(line + 1),
+                                                             None));
+            }
+        }
+        let summary = results.get_criticity_summary(false);
+        let lines: Vec<_> = summary.lines().collect();
+        let bar = |width| repeat('#').take(width).collect::<String>();
+        assert_eq!(lines,
+                   vec![format!("critical {} 1 in 1 file", bar(5)),
+                        String::from("high      0"),
+                        format!("medium   {} 4 in 1 file", bar(20)),
+                        format!("low      {} 8 in 1 file", bar(40)),
+                        String::from("warning   0")]);
+    }
+
+    #[test]
+    fn it_affected_files() {
+        let mut results: Results = Default::default();
+        let findings = [(Criticity::Critical, "Db.java", 5),
+                        (Criticity::Critical, "Db.java", 40),
+                        (Criticity::Critical, "Api.java", 12),
+                        (Criticity::Medium, "Db.java", 7),
+                        (Criticity::Low, "Api.java", 3),
+                        (Criticity::Low, "Api.java", 30)];
+        for &(criticity, file, line) in &findings {
+            results.add_vulnerability(Vulnerability::new(criticity,
+                                                         "Test vulnerability",
+                                                         "A vulnerability to count its files",
+                                                         Some(file),
+                                                         Some(line),
+                                                         Some(line),
+                                                         None));
+        }
+        results.add_vulnerability(Vulnerability::new(Criticity::Medium,
+                                                     "Manifest vulnerability",
+                                                     "A vulnerability without a file",
+                                                     None::<&str>,
+                                                     None,
+                                                     None,
+                                                     None));
+
+        assert_eq!(results.get_affected_files(Criticity::Critical), 2);
+        assert_eq!(results.get_affected_files(Criticity::High), 0);
+        assert_eq!(results.get_affected_files(Criticity::Medium), 1);
+        assert_eq!(results.get_affected_files(Criticity::Low), 1);
+        assert_eq!(results.get_affected_files(Criticity::Warning), 0);
+
+        let lines: Vec<_> = results.get_criticity_summary(false)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(lines[0], format!("critical {} 3 in 2 files",
+                                     repeat('#').take(40).collect::<String>()));
+        assert_eq!(lines[2], format!("medium   {} 2 in 1 file",
+                                     repeat('#').take(27).collect::<String>()));
+
+        let report = results.get_json_report();
+        let affected = report.find("affected_files").unwrap();
+        assert_eq!(affected.find("critical").and_then(|c| c.as_u64()), Some(2));
+        assert_eq!(affected.find("high").and_then(|c| c.as_u64()), Some(0));
+        assert_eq!(affected.find("medium").and_then(|c| c.as_u64()), Some(1));
+        assert_eq!(affected.find("low").and_then(|c| c.as_u64()), Some(1));
+        assert_eq!(affected.find("warning").and_then(|c| c.as_u64()), Some(0));
+    }
+
+    #[test]
+    fn it_txt_report() {
+        let findings = [(Criticity::Low, "log", "com/example/Utils.java", 12, "Log"),
+                        (Criticity::Critical, "sql", "com/example/Db.java", 40, "SQL injection"),
+                        (Criticity::Low, "log", "com/example/Main.java", 7, "Log"),
+                        (Criticity::Critical, "sql", "com/example/Db.java", 5, "SQL injection")];
+        let reports: Vec<_> = (0..2)
+            .map(|run| {
+                let mut results: Results = Default::default();
+                // The findings are added in a different order in each run
+                let mut ordered: Vec<_> = findings.iter().collect();
+                if run == 1 {
+                    ordered.reverse();
+                }
+                for &&(criticity, id, file, line, name) in &ordered {
+                    let mut vuln = Vulnerability::new(criticity,
+                                                      name,
+                                                      "A vulnerability to test the text report",
+                                                      Some(file),
+                                                      Some(line),
+                                                      Some(line),
+                                                      None);
+                    vuln.set_rule_id(id);
+                    results.add_vulnerability(vuln);
+                }
+                write_test_report(&results, ReportFormat::Txt)
+            })
+            .collect();
+
+        assert_eq!(reports[0].as_bytes(), reports[1].as_bytes());
+        assert_eq!(reports[0],
+                   "critical\tsql\tcom/example/Db.java:5\tSQL injection\n\
+                    critical\tsql\tcom/example/Db.java:40\tSQL injection\n\
+                    low\tlog\tcom/example/Main.java:7\tLog\n\
+                    low\tlog\tcom/example/Utils.java:12\tLog\n");
+    }
+
+    #[test]
+    fn it_json_by_file_report() {
+        let findings = [(Criticity::Low, Some("com/example/Utils.java"), 12),
+                        (Criticity::Critical, Some("com/example/Db.java"), 40),
+                        (Criticity::Low, None, 1),
+                        (Criticity::Medium, Some("com/example/Db.java"), 5),
+                        (Criticity::Warning, Some("com/example/Db.java"), 18)];
+        let mut results: Results = Default::default();
+        for &(criticity, file, line) in &findings {
+            results.add_vulnerability(Vulnerability::new(criticity,
+                                                         "Test",
+                                                         "A vulnerability to test the report",
+                                                         file,
+                                                         Some(line),
+                                                         Some(line),
+                                                         None));
+        }
+
+        let report = results.get_json_by_file_report();
+        let files = report.as_object().unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(),
+                   vec!["com/example/Db.java", "com/example/Utils.java"]);
+        let db: Vec<_> = files["com/example/Db.java"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| {
+                (v.find("start_line").and_then(|l| l.as_u64()).unwrap(),
+                 v.find("criticity").and_then(|c| c.as_str()).unwrap())
+            })
+            .collect();
+        assert_eq!(db, vec![(5, "medium"), (18, "warning"), (40, "critical")]);
+        assert_eq!(files["com/example/Utils.java"].as_array().unwrap().len(), 1);
+
+        let empty: Results = Default::default();
+        assert!(empty.get_json_by_file_report().as_object().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_report_formats() {
+        assert_eq!(ReportFormat::from_str("SARIF").unwrap(), ReportFormat::Sarif);
+        assert!(ReportFormat::from_str("xml").is_err());
+
+        let mut results: Results = Default::default();
+        results.set_app_package("com.example.formats");
+        let mut vuln = Vulnerability::new(Criticity::High,
+                                          "Test vulnerability",
+                                          "A vulnerability, to test the \"reports\"",
+                                          Some("com/example/MainActivity.java"),
+                                          Some(3),
+                                          Some(4),
+                                          None);
+        vuln.set_rule_id("test_rule");
+        vuln.set_columns(9, 21);
+        results.add_vulnerability(vuln);
+        results.add_vulnerability(Vulnerability::new(Criticity::Low,
+                                                     "Test warning",
+                                                     "A finding without a file",
+                                                     None::<&str>,
+                                                     None,
+                                                     None,
+                                                     None));
+
+        let json = write_test_report(&results, ReportFormat::Json);
+        assert_eq!(json, format!("{:?}", results.get_json_report()));
+
+        let html = write_test_report(&results, ReportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<em>com.example.formats</em>"));
+        assert!(html.ends_with("</html>"));
+
+        let csv = write_test_report(&results, ReportFormat::Csv);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0],
+                   "criticity,confidence,category,rule_id,name,file,start_line,end_line,\
+                    start_col,end_col,description");
+        assert_eq!(lines[1],
+                   "high,high,,test_rule,Test vulnerability,com/example/MainActivity.java,3,4,9,\
+                    21,\"A vulnerability, to test the \"\"reports\"\"\"");
+        assert_eq!(lines[2], "low,high,,,Test warning,,,,,,A finding without a file");
+
+        let sarif: Value =
+            serde_json::from_str(&write_test_report(&results, ReportFormat::Sarif)).unwrap();
+        assert_eq!(sarif.find("version").and_then(|v| v.as_str()), Some("2.1.0"));
+        let run = &sarif.find("runs").and_then(|r| r.as_array()).unwrap()[0];
+        assert_eq!(run.find_path(&["tool", "driver", "rules"])
+                       .and_then(|r| r.as_array())
+                       .unwrap()
+                       .len(),
+                   1);
+        let sarif_results = run.find("results").and_then(|r| r.as_array()).unwrap();
+        assert_eq!(sarif_results.len(), 2);
+        assert_eq!(sarif_results[0].find("ruleId").and_then(|r| r.as_str()),
+                   Some("test_rule"));
+        assert_eq!(sarif_results[0].find("level").and_then(|l| l.as_str()),
+                   Some("error"));
+        assert_eq!(sarif_results[0].find_path(&["locations"])
+                       .and_then(|l| l.as_array())
+                       .and_then(|l| l[0].find_path(&["physicalLocation", "region", "startLine"]))
+                       .and_then(|l| l.as_u64()),
+                   Some(3));
+        assert_eq!(sarif_results[0].find_path(&["locations"])
+                       .and_then(|l| l.as_array())
+                       .and_then(|l| {
+                           l[0].find_path(&["physicalLocation", "region", "startColumn"])
+                       })
+                       .and_then(|c| c.as_u64()),
+                   Some(9));
+        assert_eq!(sarif_results[1].find("level").and_then(|l| l.as_str()), Some("note"));
+        assert!(sarif_results[1].find("locations").is_none());
+        assert!(run.find("properties").is_none());
+    }
+
+    #[test]
+    fn it_external_findings() {
+        let mut results: Results = Default::default();
+        results.set_app_package("com.example.external");
+        results.add_vulnerability(VulnerabilityBuilder::new(Criticity::Critical,
+                                                            "Hardcoded key",
+                                                            "The application has a hardcoded \
+                                                             key.")
+            .confidence(Confidence::Medium)
+            .category("crypto")
+            .message("The AES key is hardcoded")
+            .file("com/example/Keys.java")
+            .lines(12, 12)
+            .columns(18, 34)
+            .code("class Keys {\n    byte[] key = KEY;\n}")
+            .rule_id("external_hardcoded_key")
+            .secret(true)
+            .build());
+        results.add_vulnerability(VulnerabilityBuilder::new(Criticity::Warning,
+                                                            "Old dependency",
+                                                            "A dependency is outdated.")
+            .build());
+        assert_eq!(results.get_total_findings(), 2);
+
+        let json: Value =
+            serde_json::from_str(&write_test_report(&results, ReportFormat::Json)).unwrap();
+        let critical = json.find("critical").and_then(|c| c.as_array()).unwrap();
+        assert_eq!(critical.len(), 1);
+        let key = &critical[0];
+        assert_eq!(key.find("confidence").and_then(|c| c.as_str()), Some("medium"));
+        assert_eq!(key.find("category").and_then(|c| c.as_str()), Some("crypto"));
+        assert_eq!(key.find("message").and_then(|m| m.as_str()),
+                   Some("The AES key is hardcoded"));
+        assert_eq!(key.find("file").and_then(|f| f.as_str()), Some("com/example/Keys.java"));
+        assert_eq!(key.find("start_line").and_then(|l| l.as_u64()), Some(12));
+        assert_eq!(key.find("end_col").and_then(|c| c.as_u64()), Some(34));
+        assert_eq!(key.find("rule_id").and_then(|r| r.as_str()),
+                   Some("external_hardcoded_key"));
+        let warnings = json.find("warnings").and_then(|w| w.as_array()).unwrap();
+        assert_eq!(warnings[0].find("message").and_then(|m| m.as_str()),
+                   Some("A dependency is outdated."));
+
+        let sarif: Value =
+            serde_json::from_str(&write_test_report(&results, ReportFormat::Sarif)).unwrap();
+        let run = &sarif.find("runs").and_then(|r| r.as_array()).unwrap()[0];
+        let sarif_results = run.find("results").and_then(|r| r.as_array()).unwrap();
+        assert_eq!(sarif_results.len(), 2);
+        assert_eq!(sarif_results[0].find("ruleId").and_then(|r| r.as_str()),
+                   Some("external_hardcoded_key"));
+
+        let html = write_test_report(&results, ReportFormat::Html);
+        assert!(html.contains("<em>com.example.external</em>"));
+        assert!(html.contains("Hardcoded key"));
+    }
+}
@@ -0,0 +1,326 @@
+use std::result;
+use std::str::FromStr;
+use std::cmp::Ordering;
+
+use Criticity;
+use super::Vulnerability;
+
+/// Symbols of the selection expressions. The longer symbols go first, so that `>=` is not read
+/// as `>` followed by `=`.
+const SYMBOLS: [&'static str; 13] = ["&&", "||", "==", "!=", ">=", "<=", "!~", "!", "(", ")",
+                                     ">", "<", "~"];
+
+/// Token of a selection expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Symbol(&'static str),
+}
+
+/// Text field of the findings that can be compared in a selection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    File,
+    Rule,
+    Label,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Contains,
+    NotContains,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Criticity(Operator, Criticity),
+    Text(Field, Operator, String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn matches(&self, vuln: &Vulnerability) -> bool {
+        match *self {
+            Expr::Criticity(operator, criticity) => {
+                let ordering = vuln.get_criticity().cmp(&criticity);
+                match operator {
+                    Operator::Equal => ordering == Ordering::Equal,
+                    Operator::NotEqual => ordering != Ordering::Equal,
+                    Operator::Greater => ordering == Ordering::Greater,
+                    Operator::GreaterOrEqual => ordering != Ordering::Less,
+                    Operator::Less => ordering == Ordering::Less,
+                    Operator::LessOrEqual => ordering != Ordering::Greater,
+                    Operator::Contains | Operator::NotContains => false,
+                }
+            }
+            Expr::Text(field, operator, ref value) => {
+                let text = match field {
+                    Field::File => {
+                        vuln.get_file().map_or(String::new(), |f| f.display().to_string())
+                    }
+                    Field::Rule => String::from(vuln.get_rule_id().unwrap_or("")),
+                    Field::Label => String::from(vuln.get_name()),
+                };
+                let contains = text.to_lowercase().contains(&value.to_lowercase());
+                match operator {
+                    Operator::Equal => text == *value,
+                    Operator::NotEqual => text != *value,
+                    Operator::Contains => contains,
+                    Operator::NotContains => !contains,
+                    _ => false,
+                }
+            }
+            Expr::Not(ref expr) => !expr.matches(vuln),
+            Expr::And(ref left, ref right) => left.matches(vuln) && right.matches(vuln),
+            Expr::Or(ref left, ref right) => left.matches(vuln) || right.matches(vuln),
+        }
+    }
+}
+
+/// Selection of findings, given by an expression such as `criticity>=high && file~"payment"`.
+///
+/// The expressions compare the `criticity`, `file`, `rule` (the rule ID) and `label` (the name
+/// of the finding) fields with a value, which can be quoted if it has spaces or symbols:
+///
+/// ```text
+/// expr       := and ("||" and)*
+/// and        := unary ("&&" unary)*
+/// unary      := "!" unary | "(" expr ")" | comparison
+/// comparison := field operator value
+/// ```
+///
+/// The criticity can be compared with `==`, `!=`, `<`, `<=`, `>` and `>=`, from `warning` to
+/// `critical`. The rest of fields can be compared with `==` and `!=`, or checked with `~` and
+/// `!~` for whether they contain the value, ignoring case. Findings without a file or rule ID
+/// have an empty one.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    expr: Expr,
+}
+
+impl Selector {
+    /// Parses the given selection expression, returning why it is not valid if it fails.
+    pub fn new(expr: &str) -> result::Result<Selector, String> {
+        let mut parser = Parser {
+            tokens: try!(tokenize(expr)),
+            pos: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(String::from("the selection is empty"));
+        }
+        let expr = try!(parser.parse_or());
+        if parser.pos < parser.tokens.len() {
+            return Err(String::from("unexpected text after the end of the selection"));
+        }
+        Ok(Selector { expr: expr })
+    }
+
+    /// Checks if the given finding is selected.
+    pub fn matches(&self, vuln: &Vulnerability) -> bool {
+        self.expr.matches(vuln)
+    }
+}
+
+fn tokenize(expr: &str) -> result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_left();
+    while !rest.is_empty() {
+        if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(*symbol));
+            rest = &rest[symbol.len()..];
+        } else if rest.starts_with('"') {
+            match rest[1..].find('"') {
+                Some(end) => {
+                    tokens.push(Token::Quoted(String::from(&rest[1..end + 1])));
+                    rest = &rest[end + 2..];
+                }
+                None => return Err(String::from("a quoted value is not closed")),
+            }
+        } else {
+            let end = rest.find(|c: char| !c.is_alphanumeric() && !"_-./$".contains(c))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected character `{}`", rest.chars().next().unwrap()));
+            }
+            tokens.push(Token::Word(String::from(&rest[..end])));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_left();
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser of the selection expressions.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Skips the next token if it is the given symbol, returning if it was.
+    fn eat(&mut self, symbol: &str) -> bool {
+        match self.tokens.get(self.pos) {
+            Some(&Token::Symbol(s)) if s == symbol => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> result::Result<Expr, String> {
+        let mut expr = try!(self.parse_and());
+        while self.eat("||") {
+            let right = try!(self.parse_and());
+            expr = Expr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> result::Result<Expr, String> {
+        let mut expr = try!(self.parse_unary());
+        while self.eat("&&") {
+            let right = try!(self.parse_unary());
+            expr = Expr::And(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> result::Result<Expr, String> {
+        if self.eat("!") {
+            Ok(Expr::Not(Box::new(try!(self.parse_unary()))))
+        } else if self.eat("(") {
+            let expr = try!(self.parse_or());
+            if self.eat(")") {
+                Ok(expr)
+            } else {
+                Err(String::from("a parenthesis is not closed"))
+            }
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    fn parse_comparison(&mut self) -> result::Result<Expr, String> {
+        let field = match self.next() {
+            Some(Token::Word(w)) => w,
+            _ => {
+                return Err(String::from("expected a field, one of criticity, file, rule or \
+                                         label"))
+            }
+        };
+        let operator = match self.next() {
+            Some(Token::Symbol("==")) => Operator::Equal,
+            Some(Token::Symbol("!=")) => Operator::NotEqual,
+            Some(Token::Symbol(">")) => Operator::Greater,
+            Some(Token::Symbol(">=")) => Operator::GreaterOrEqual,
+            Some(Token::Symbol("<")) => Operator::Less,
+            Some(Token::Symbol("<=")) => Operator::LessOrEqual,
+            Some(Token::Symbol("~")) => Operator::Contains,
+            Some(Token::Symbol("!~")) => Operator::NotContains,
+            _ => return Err(format!("expected an operator after the {} field", field)),
+        };
+        let value = match self.next() {
+            Some(Token::Word(v)) |
+            Some(Token::Quoted(v)) => v,
+            _ => return Err(format!("expected a value to compare the {} field with", field)),
+        };
+
+        let field = match field.as_str() {
+            "criticity" => {
+                let criticity = match Criticity::from_str(value.as_str()) {
+                    Ok(c) => c,
+                    Err(_) => {
+                        return Err(format!("the criticity {} must be one of warning, low, \
+                                            medium, high or critical",
+                                           value))
+                    }
+                };
+                return match operator {
+                    Operator::Contains | Operator::NotContains => {
+                        Err(String::from("the criticity can only be compared with ==, !=, <, \
+                                          <=, > or >="))
+                    }
+                    _ => Ok(Expr::Criticity(operator, criticity)),
+                };
+            }
+            "file" => Field::File,
+            "rule" => Field::Rule,
+            "label" => Field::Label,
+            f => {
+                return Err(format!("unknown field {}, it must be one of criticity, file, rule \
+                                    or label",
+                                   f))
+            }
+        };
+        match operator {
+            Operator::Equal | Operator::NotEqual | Operator::Contains | Operator::NotContains => {
+                Ok(Expr::Text(field, operator, value))
+            }
+            _ => {
+                Err(String::from("the file, rule and label can only be compared with ==, !=, ~ \
+                                  or !~"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selector;
+    use super::super::{Vulnerability, VulnerabilityBuilder};
+
+    use Criticity;
+
+    fn vuln(criticity: Criticity, file: &str, rule_id: &str) -> Vulnerability {
+        VulnerabilityBuilder::new(criticity, "Hardcoded secret", "A secret in the code")
+            .file(file)
+            .rule_id(rule_id)
+            .build()
+    }
+
+    #[test]
+    fn it_selector() {
+        let payment = vuln(Criticity::High, "classes/com/example/PaymentActivity.java", "jwt");
+        let critical = vuln(Criticity::Critical, "classes/com/example/Login.java", "jwt");
+        let low = vuln(Criticity::Low, "classes/com/example/Payments.java", "logging");
+
+        let selector = Selector::new("criticity>=high && file~\"payment\"").unwrap();
+        assert!(selector.matches(&payment));
+        assert!(!selector.matches(&critical));
+        assert!(!selector.matches(&low));
+
+        let selector = Selector::new("rule == logging || !(criticity < critical)").unwrap();
+        assert!(!selector.matches(&payment));
+        assert!(selector.matches(&critical));
+        assert!(selector.matches(&low));
+
+        let selector = Selector::new("label ~ \"HARDCODED SECRET\" && file !~ login").unwrap();
+        assert!(selector.matches(&payment));
+        assert!(!selector.matches(&critical));
+
+        assert!(Selector::new("").is_err());
+        assert!(Selector::new("criticity >= severe").is_err());
+        assert!(Selector::new("criticity ~ high").is_err());
+        assert!(Selector::new("file > a").is_err());
+        assert!(Selector::new("package == com.example").is_err());
+        assert!(Selector::new("(criticity == low").is_err());
+        assert!(Selector::new("file ~ \"payment").is_err());
+        assert!(Selector::new("criticity == low low").is_err());
+    }
+}
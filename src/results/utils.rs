@@ -12,22 +12,50 @@ use crypto::sha1::Sha1;
 use crypto::sha2::Sha256;
 use rustc_serialize::hex::ToHex;
 
-use {Config, Result, Criticity};
+use {Config, Result, Criticity, Confidence, get_snippet};
 
 /// Structure to store information about a vulnerability
 #[derive(Debug, Clone, PartialEq, Eq, Ord)]
 pub struct Vulnerability {
     criticity: Criticity,
+    confidence: Confidence,
+    category: Option<String>,
     name: String,
     description: String,
+    message: Option<String>,
     file: Option<String>,
+    /// First line of the vulnerable code, starting at 1.
     start_line: Option<usize>,
+    /// Last line of the vulnerable code, starting at 1.
     end_line: Option<usize>,
+    /// Column of the first character of the vulnerable code in its first line, starting at 1.
+    start_col: Option<usize>,
+    /// Column of the character following the vulnerable code in its last line, starting at 1.
+    end_col: Option<usize>,
+    /// Byte offset of the first byte of the vulnerable code in the decoded source file.
+    start_offset: Option<usize>,
+    /// Byte offset of the byte following the vulnerable code in the decoded source file.
+    end_offset: Option<usize>,
     code: Option<String>,
+    rule_id: Option<String>,
+    /// Position of the rule that found the vulnerability among the loaded rules, as authored.
+    rule_index: Option<usize>,
+    /// Whether the vulnerable code is a secret, such as a key, that could be leaked in a report.
+    secret: bool,
+    /// SHA-256 hash of the secret literal of the vulnerable code, to find the same secret in other
+    /// files without keeping it.
+    secret_hash: Option<String>,
 }
 
 impl Vulnerability {
     /// Creates a new vulnerability
+    ///
+    /// The `name` is the label of the rule or check that found it, and the `description`
+    /// explains the problem. The `file` is relative to the source folder of the application, and
+    /// the lines start at 1. The `code` is shown in the HTML report, and it should include up to
+    /// 4 lines of context before the vulnerable lines, as in the findings of the code rules. The
+    /// confidence is high by default. The rest of the fields can be set afterwards, or with a
+    /// `VulnerabilityBuilder`.
     pub fn new<S: AsRef<str>, P: AsRef<Path>>(criticity: Criticity,
                                               name: S,
                                               description: S,
@@ -38,18 +66,29 @@ impl Vulnerability {
                                               -> Vulnerability {
         Vulnerability {
             criticity: criticity,
+            confidence: Confidence::High,
+            category: None,
             name: String::from(name.as_ref()),
             description: String::from(description.as_ref()),
+            message: None,
             file: match file {
                 Some(s) => Some(String::from(s.as_ref().to_string_lossy().into_owned())),
                 None => None,
             },
             start_line: start_line,
             end_line: end_line,
+            start_col: None,
+            end_col: None,
+            start_offset: None,
+            end_offset: None,
             code: match code {
                 Some(s) => Some(String::from(s.as_ref() as &str)),
                 None => None,
             },
+            rule_id: None,
+            rule_index: None,
+            secret: false,
+            secret_hash: None,
         }
     }
 
@@ -58,6 +97,29 @@ impl Vulnerability {
         self.criticity
     }
 
+    /// Gets the confidence of the vulnerability
+    pub fn get_confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    /// Sets the confidence of the vulnerability
+    pub fn set_confidence(&mut self, confidence: Confidence) {
+        self.confidence = confidence;
+    }
+
+    /// Gets the category of the rule that found the vulnerability, if any
+    pub fn get_category(&self) -> Option<&str> {
+        match self.category.as_ref() {
+            Some(c) => Some(c.as_str()),
+            None => None,
+        }
+    }
+
+    /// Sets the category of the vulnerability
+    pub fn set_category<S: AsRef<str>>(&mut self, category: S) {
+        self.category = Some(String::from(category.as_ref()));
+    }
+
     /// Gets the name of the vulnerability
     pub fn get_name(&self) -> &str {
         self.name.as_str()
@@ -68,6 +130,20 @@ impl Vulnerability {
         self.description.as_str()
     }
 
+    /// Gets the message of this finding of the vulnerability, or the description if the rule
+    /// that found it has no message
+    pub fn get_message(&self) -> &str {
+        match self.message.as_ref() {
+            Some(m) => m.as_str(),
+            None => self.description.as_str(),
+        }
+    }
+
+    /// Sets the message of this finding of the vulnerability
+    pub fn set_message<S: AsRef<str>>(&mut self, message: S) {
+        self.message = Some(String::from(message.as_ref()));
+    }
+
     /// Gets the file where the vulnerability was found
     pub fn get_file(&self) -> Option<&Path> {
         match self.file.as_ref() {
@@ -76,6 +152,11 @@ impl Vulnerability {
         }
     }
 
+    /// Sets the file where the vulnerability was found
+    pub fn set_file<P: AsRef<Path>>(&mut self, file: P) {
+        self.file = Some(file.as_ref().to_string_lossy().into_owned());
+    }
+
     /// Gets the code related to the vulnerability
     pub fn get_code(&self) -> Option<&str> {
         match self.code.as_ref() {
@@ -93,19 +174,266 @@ impl Vulnerability {
     pub fn get_end_line(&self) -> Option<usize> {
         self.end_line
     }
+
+    /// Gets the start column of the vulnerability
+    ///
+    /// Columns are counted in characters, not bytes, starting at 1.
+    pub fn get_start_col(&self) -> Option<usize> {
+        self.start_col
+    }
+
+    /// Gets the end column of the vulnerability, the one of the character following it
+    pub fn get_end_col(&self) -> Option<usize> {
+        self.end_col
+    }
+
+    /// Sets the start and end columns of the vulnerability
+    pub fn set_columns(&mut self, start_col: usize, end_col: usize) {
+        self.start_col = Some(start_col);
+        self.end_col = Some(end_col);
+    }
+
+    /// Gets the byte offset of the start of the vulnerable code in the source file
+    ///
+    /// The offsets are the ones of the source file decoded as UTF-8, so that `&code[start..end]`
+    /// is the matched code.
+    pub fn get_start_offset(&self) -> Option<usize> {
+        self.start_offset
+    }
+
+    /// Gets the byte offset following the end of the vulnerable code in the source file
+    pub fn get_end_offset(&self) -> Option<usize> {
+        self.end_offset
+    }
+
+    /// Sets the start and end byte offsets of the vulnerability
+    pub fn set_offsets(&mut self, start_offset: usize, end_offset: usize) {
+        self.start_offset = Some(start_offset);
+        self.end_offset = Some(end_offset);
+    }
+
+    /// Gets the ID of the rule that found the vulnerability, if any
+    pub fn get_rule_id(&self) -> Option<&str> {
+        match self.rule_id.as_ref() {
+            Some(id) => Some(id.as_str()),
+            None => None,
+        }
+    }
+
+    /// Sets the ID of the rule that found the vulnerability
+    pub fn set_rule_id<S: AsRef<str>>(&mut self, rule_id: S) {
+        self.rule_id = Some(String::from(rule_id.as_ref()));
+    }
+
+    /// Gets the position of the rule that found the vulnerability among the loaded rules, if
+    /// any, starting at 0.
+    ///
+    /// The rules keep the order of the rule files, and of the rules in each file, so the index
+    /// can be correlated with the authored rules even if some of them were filtered out.
+    pub fn get_rule_index(&self) -> Option<usize> {
+        self.rule_index
+    }
+
+    /// Sets the position of the rule that found the vulnerability among the loaded rules
+    pub fn set_rule_index(&mut self, rule_index: usize) {
+        self.rule_index = Some(rule_index);
+    }
+
+    /// Checks if the vulnerable code is a secret
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    /// Sets if the vulnerable code is a secret
+    pub fn set_secret(&mut self, secret: bool) {
+        self.secret = secret;
+    }
+
+    /// Gets the SHA-256 hash of the secret of the vulnerable code, if it is a secret
+    pub fn get_secret_hash(&self) -> Option<&str> {
+        match self.secret_hash.as_ref() {
+            Some(h) => Some(h.as_str()),
+            None => None,
+        }
+    }
+
+    /// Sets the SHA-256 hash of the secret of the vulnerable code
+    pub fn set_secret_hash<S: AsRef<str>>(&mut self, secret_hash: S) {
+        self.secret_hash = Some(String::from(secret_hash.as_ref()));
+    }
+
+    /// Extracts the code of the vulnerability again from the given source of its file, such as
+    /// a copy with its secrets masked by `redact_secrets()`.
+    ///
+    /// The secrets must be masked in the whole source, and not in the code of the vulnerability,
+    /// since long lines are truncated around the vulnerable code when it is extracted, and the
+    /// columns of the secrets would not match the truncated lines. Vulnerabilities without lines
+    /// keep their code.
+    pub fn set_code_from_source(&mut self, source: &str) {
+        if let (Some(start_line), Some(end_line)) = (self.start_line, self.end_line) {
+            let start = (start_line, self.start_col.unwrap_or(1));
+            let end = (end_line, self.end_col.unwrap_or(1));
+            self.code = Some(get_snippet(source, start, end));
+        }
+    }
+
+    /// Removes the code of the vulnerability, so that it is not shown in the reports.
+    pub fn remove_code(&mut self) {
+        self.code = None;
+    }
+
+    /// Gets the fingerprint of the vulnerability, to identify it between analyses.
+    ///
+    /// It is the SHA-256 hash of the rule ID (or the name, if there is no rule), the file and the
+    /// trimmed vulnerable lines. Line numbers are not used, so that the fingerprint does not change
+    /// when code is added or removed before the vulnerability. Vulnerabilities without code, such
+    /// as the ones of secrets duplicated across files, use the hash of their secret instead, if
+    /// they have one.
+    pub fn get_fingerprint(&self) -> String {
+        let mut sha256 = Sha256::new();
+        sha256.input_str(self.get_rule_id().unwrap_or(self.name.as_str()));
+        sha256.input(&[0]);
+        sha256.input_str(self.file.as_ref().map(|f| f.as_str()).unwrap_or(""));
+        sha256.input(&[0]);
+        if let (Some(code), Some(start_line), Some(end_line)) =
+               (self.code.as_ref(), self.start_line, self.end_line) {
+            // The code has up to 4 lines of context before the vulnerable lines.
+            let first_line = if start_line > 4 { start_line - 4 } else { 1 };
+            for line in code.lines()
+                .skip(start_line.saturating_sub(first_line))
+                .take(end_line.saturating_sub(start_line) + 1) {
+                sha256.input_str(line.trim());
+                sha256.input(&[b'\n']);
+            }
+        } else if let Some(hash) = self.get_secret_hash() {
+            sha256.input_str(hash);
+        }
+        let mut hash = [0u8; 32];
+        sha256.result(&mut hash);
+        hash.to_hex()
+    }
+}
+
+/// Builder of vulnerabilities, used to build the findings of the rules field by field, without
+/// giving all of them at once to `Vulnerability::new()`.
+///
+/// ```
+/// use results::{Results, ReportFormat, VulnerabilityBuilder};
+///
+/// let mut results: Results = Default::default();
+/// results.add_vulnerability(VulnerabilityBuilder::new(Criticity::High,
+///                                                     "Hardcoded key",
+///                                                     "The application has a hardcoded key.")
+///     .file("com/example/Keys.java")
+///     .lines(12, 12)
+///     .rule_id("hardcoded_key")
+///     .build());
+/// results.write_report(ReportFormat::Sarif, &mut io::stdout()).unwrap();
+/// ```
+pub struct VulnerabilityBuilder {
+    vulnerability: Vulnerability,
+}
+
+impl VulnerabilityBuilder {
+    /// Starts building a vulnerability with the given criticity, name and description, without
+    /// a file.
+    pub fn new<S: AsRef<str>>(criticity: Criticity,
+                              name: S,
+                              description: S)
+                              -> VulnerabilityBuilder {
+        VulnerabilityBuilder {
+            vulnerability: Vulnerability::new(criticity,
+                                              name,
+                                              description,
+                                              None::<&str>,
+                                              None,
+                                              None,
+                                              None),
+        }
+    }
+
+    /// Sets the confidence of the vulnerability, high by default.
+    pub fn confidence(mut self, confidence: Confidence) -> VulnerabilityBuilder {
+        self.vulnerability.set_confidence(confidence);
+        self
+    }
+
+    /// Sets the category of the vulnerability.
+    pub fn category<S: AsRef<str>>(mut self, category: S) -> VulnerabilityBuilder {
+        self.vulnerability.set_category(category);
+        self
+    }
+
+    /// Sets the message of this finding of the vulnerability, instead of its description.
+    pub fn message<S: AsRef<str>>(mut self, message: S) -> VulnerabilityBuilder {
+        self.vulnerability.set_message(message);
+        self
+    }
+
+    /// Sets the file of the vulnerability, relative to the source folder of the application.
+    pub fn file<P: AsRef<Path>>(mut self, file: P) -> VulnerabilityBuilder {
+        self.vulnerability.set_file(file);
+        self
+    }
+
+    /// Sets the first and last lines of the vulnerable code, starting at 1.
+    pub fn lines(mut self, start_line: usize, end_line: usize) -> VulnerabilityBuilder {
+        self.vulnerability.start_line = Some(start_line);
+        self.vulnerability.end_line = Some(end_line);
+        self
+    }
+
+    /// Sets the columns of the vulnerable code, as in `Vulnerability::set_columns()`.
+    pub fn columns(mut self, start_col: usize, end_col: usize) -> VulnerabilityBuilder {
+        self.vulnerability.set_columns(start_col, end_col);
+        self
+    }
+
+    /// Sets the vulnerable code, with up to 4 lines of context before the vulnerable lines.
+    pub fn code<S: AsRef<str>>(mut self, code: S) -> VulnerabilityBuilder {
+        self.vulnerability.code = Some(String::from(code.as_ref()));
+        self
+    }
+
+    /// Sets the ID of the rule that found the vulnerability.
+    pub fn rule_id<S: AsRef<str>>(mut self, rule_id: S) -> VulnerabilityBuilder {
+        self.vulnerability.set_rule_id(rule_id);
+        self
+    }
+
+    /// Sets if the vulnerable code is a secret, to mask it when secrets are redacted.
+    pub fn secret(mut self, secret: bool) -> VulnerabilityBuilder {
+        self.vulnerability.set_secret(secret);
+        self
+    }
+
+    /// Builds the vulnerability.
+    pub fn build(self) -> Vulnerability {
+        self.vulnerability
+    }
 }
 
 impl Serialize for Vulnerability {
     fn serialize<S>(&self, serializer: &mut S) -> result::Result<(), S::Error>
         where S: Serializer
     {
-        let mut state = try!(serializer.serialize_struct("Vulnerability", 7));
+        let mut state = try!(serializer.serialize_struct("Vulnerability", 16));
         try!(serializer.serialize_struct_elt(&mut state, "criticity", self.criticity));
+        try!(serializer.serialize_struct_elt(&mut state, "confidence", self.confidence));
+        try!(serializer.serialize_struct_elt(&mut state, "category", &self.category));
         try!(serializer.serialize_struct_elt(&mut state, "name", self.name.as_str()));
         try!(serializer.serialize_struct_elt(&mut state, "description", self.description.as_str()));
+        try!(serializer.serialize_struct_elt(&mut state, "message", self.get_message()));
         try!(serializer.serialize_struct_elt(&mut state, "file", &self.file));
         try!(serializer.serialize_struct_elt(&mut state, "start_line", self.start_line));
         try!(serializer.serialize_struct_elt(&mut state, "end_line", self.end_line));
+        try!(serializer.serialize_struct_elt(&mut state, "start_col", self.start_col));
+        try!(serializer.serialize_struct_elt(&mut state, "end_col", self.end_col));
+        try!(serializer.serialize_struct_elt(&mut state, "start_offset", self.start_offset));
+        try!(serializer.serialize_struct_elt(&mut state, "end_offset", self.end_offset));
+        try!(serializer.serialize_struct_elt(&mut state, "rule_id", &self.rule_id));
+        try!(serializer.serialize_struct_elt(&mut state, "rule_index", self.rule_index));
+        try!(serializer.serialize_struct_elt(&mut state, "fingerprint", self.get_fingerprint()));
         try!(serializer.serialize_struct_end(state));
         Ok(())
     }
@@ -142,6 +470,7 @@ impl PartialOrd for Vulnerability {
 }
 
 /// Structure to store
+#[derive(Default)]
 pub struct FingerPrint {
     md5: [u8; 16],
     sha1: [u8; 20],
@@ -222,13 +551,35 @@ impl Benchmark {
             duration: duration,
         }
     }
+
+    /// Gets the label of the benchmark
+    pub fn get_label(&self) -> &str {
+        self.label.as_str()
+    }
+
+    /// Gets the duration of the benchmark in milliseconds
+    pub fn get_millis(&self) -> u64 {
+        self.duration.as_secs() * 1000 + self.duration.subsec_nanos() as u64 / 1_000_000
+    }
+}
+
+impl Serialize for Benchmark {
+    fn serialize<S>(&self, serializer: &mut S) -> result::Result<(), S::Error>
+        where S: Serializer
+    {
+        let mut state = try!(serializer.serialize_struct("Benchmark", 2));
+        try!(serializer.serialize_struct_elt(&mut state, "label", self.get_label()));
+        try!(serializer.serialize_struct_elt(&mut state, "duration_ms", self.get_millis()));
+        try!(serializer.serialize_struct_end(state));
+        Ok(())
+    }
 }
 
 impl fmt::Display for Benchmark {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
         write!(f,
                "{}: {}.{}s",
-               self.label,
+               self.get_label(),
                self.duration.as_secs(),
                self.duration.subsec_nanos())
     }
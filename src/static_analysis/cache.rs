@@ -0,0 +1,334 @@
+use std::fs;
+use std::fs::{File, Metadata};
+use std::io::Write;
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use std::collections::BTreeMap;
+
+use serde_json;
+use serde_json::builder::ObjectBuilder;
+use serde_json::value::Value;
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
+use {Config, Result, Criticity, Confidence, file_exists};
+use results::Vulnerability;
+use super::code::FileFindings;
+use super::manifest::Manifest;
+
+/// Version of the cache format. Caches generated with other versions are discarded.
+const CACHE_VERSION: u32 = 6;
+
+/// Cache of the code analysis findings of each file.
+///
+/// Files are identified by their path in the decompiled application and the hash of their
+/// contents. The whole cache is discarded if the selected rules, the analyzed folder or the
+/// manifest of the application change, since the findings could be different even for the same
+/// files.
+pub struct Cache {
+    path: PathBuf,
+    context_hash: String,
+    previous: BTreeMap<String, CachedFile>,
+    current: BTreeMap<String, CachedFile>,
+}
+
+#[derive(Clone)]
+struct CachedFile {
+    size: u64,
+    modified: u64,
+    hash: String,
+    findings: FileFindings,
+}
+
+impl Cache {
+    /// Loads the cache of the application from the cache folder.
+    ///
+    /// If there is no cache for the application, or if it was generated with different rules, an
+    /// empty cache will be returned. The `rules_hash` must identify the rules used in the
+    /// analysis, and the `manifest` must be the one given to the rules, if any.
+    pub fn load(config: &Config, rules_hash: &str, manifest: Option<&Manifest>) -> Result<Cache> {
+        let mut cache = Cache {
+            path: Path::new(config.get_cache_folder())
+                .join(format!("{}.json", config.get_app_id())),
+            context_hash: get_context_hash(config, rules_hash, manifest),
+            previous: BTreeMap::new(),
+            current: BTreeMap::new(),
+        };
+
+        if !cache.path.exists() {
+            return Ok(cache);
+        }
+
+        let f = try!(File::open(&cache.path));
+        let json: Value = try!(serde_json::from_reader(f));
+        if json.find("context").and_then(|r| r.as_str()) != Some(cache.context_hash.as_str()) {
+            return Ok(cache);
+        }
+
+        if let Some(files) = json.find("files").and_then(|f| f.as_object()) {
+            for (file, entry) in files {
+                if let Some(cached) = CachedFile::from_json(entry) {
+                    let _ = cache.previous.insert(file.clone(), cached);
+                }
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Gets the cached findings of the file if its size and modification time did not change.
+    ///
+    /// If found, the findings will be kept in the cache for the next analysis.
+    pub fn get(&mut self, file: &str, metadata: &Metadata) -> Option<FileFindings> {
+        let modified = get_modified(metadata);
+        let cached = match self.previous.get(file) {
+            Some(cached) if modified != 0 && cached.size == metadata.len() &&
+                            cached.modified == modified => cached.clone(),
+            _ => return None,
+        };
+        let findings = cached.findings.clone();
+        let _ = self.current.insert(String::from(file), cached);
+        Some(findings)
+    }
+
+    /// Gets the cached findings of the file if its contents did not change.
+    ///
+    /// If found, the findings will be kept in the cache for the next analysis, with the new size
+    /// and modification time of the file.
+    pub fn get_by_hash(&mut self,
+                       file: &str,
+                       metadata: &Metadata,
+                       hash: &str)
+                       -> Option<FileFindings> {
+        let findings = match self.previous.get(file) {
+            Some(cached) if cached.hash == hash => cached.findings.clone(),
+            _ => return None,
+        };
+        self.insert(file, metadata, hash, &findings);
+        Some(findings)
+    }
+
+    /// Stores the findings of the file for the next analysis.
+    pub fn insert(&mut self, file: &str, metadata: &Metadata, hash: &str, findings: &FileFindings) {
+        let _ = self.current.insert(String::from(file),
+                                    CachedFile {
+                                        size: metadata.len(),
+                                        modified: get_modified(metadata),
+                                        hash: String::from(hash),
+                                        findings: findings.clone(),
+                                    });
+    }
+
+    /// Saves the cache in the cache folder.
+    ///
+    /// Only the files inserted during this analysis will be saved, so that files removed from the
+    /// application do not stay in the cache forever.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !file_exists(parent) {
+                try!(fs::create_dir_all(parent));
+            }
+        }
+
+        let json = ObjectBuilder::new()
+            .insert("context", self.context_hash.as_str())
+            .insert_object("files", |builder| {
+                let mut builder = builder;
+                for (file, cached) in &self.current {
+                    builder = builder.insert(file.as_str(), cached.to_json());
+                }
+                builder
+            })
+            .build();
+
+        let mut f = try!(File::create(&self.path));
+        try!(f.write_all(format!("{:?}", json).as_bytes()));
+        Ok(())
+    }
+}
+
+impl CachedFile {
+    fn to_json(&self) -> Value {
+        ObjectBuilder::new()
+            .insert("size", self.size)
+            .insert("modified", self.modified)
+            .insert("hash", self.hash.as_str())
+            .insert("lines", self.findings.get_lines() as u64)
+            .insert_array("vulnerabilities", |builder| {
+                let mut builder = builder;
+                for vuln in self.findings.get_vulnerabilities() {
+                    builder = builder.push(ObjectBuilder::new()
+                        .insert("criticity", vuln.get_criticity())
+                        .insert("confidence", vuln.get_confidence())
+                        .insert("category", vuln.get_category())
+                        .insert("name", vuln.get_name())
+                        .insert("description", vuln.get_description())
+                        .insert("message", vuln.get_message())
+                        .insert("file",
+                                vuln.get_file().map(|f| f.to_string_lossy().into_owned()))
+                        .insert("start_line", vuln.get_start_line())
+                        .insert("end_line", vuln.get_end_line())
+                        .insert("start_col", vuln.get_start_col())
+                        .insert("end_col", vuln.get_end_col())
+                        .insert("start_offset", vuln.get_start_offset())
+                        .insert("end_offset", vuln.get_end_offset())
+                        .insert("code", vuln.get_code())
+                        .insert("rule_id", vuln.get_rule_id())
+                        .insert("rule_index", vuln.get_rule_index())
+                        .insert("secret", vuln.is_secret())
+                        .insert("secret_hash", vuln.get_secret_hash())
+                        .build());
+                }
+                builder
+            })
+            .insert_array("whitelisted_rules", |builder| {
+                let mut builder = builder;
+                for rule in self.findings.get_whitelisted_rules() {
+                    builder = builder.push(rule);
+                }
+                builder
+            })
+            .build()
+    }
+
+    fn from_json(json: &Value) -> Option<CachedFile> {
+        let vulns = match json.find("vulnerabilities").and_then(|v| v.as_array()) {
+            Some(v) => v,
+            None => return None,
+        };
+
+        let mut vulnerabilities = Vec::with_capacity(vulns.len());
+        for vuln in vulns {
+            let criticity = match vuln.find("criticity")
+                .and_then(|c| c.as_str())
+                .and_then(|c| Criticity::from_str(c).ok()) {
+                Some(c) => c,
+                None => return None,
+            };
+            let confidence = match vuln.find("confidence")
+                .and_then(|c| c.as_str())
+                .and_then(|c| Confidence::from_str(c).ok()) {
+                Some(c) => c,
+                None => return None,
+            };
+            let name = match vuln.find("name").and_then(|n| n.as_str()) {
+                Some(n) => n,
+                None => return None,
+            };
+            let description = match vuln.find("description").and_then(|d| d.as_str()) {
+                Some(d) => d,
+                None => return None,
+            };
+
+            let mut vulnerability =
+                Vulnerability::new(criticity,
+                                   name,
+                                   description,
+                                   vuln.find("file").and_then(|f| f.as_str()),
+                                   vuln.find("start_line")
+                                       .and_then(|l| l.as_u64())
+                                       .map(|l| l as usize),
+                                   vuln.find("end_line")
+                                       .and_then(|l| l.as_u64())
+                                       .map(|l| l as usize),
+                                   vuln.find("code").and_then(|c| c.as_str()).map(String::from));
+            vulnerability.set_confidence(confidence);
+            if let Some(category) = vuln.find("category").and_then(|c| c.as_str()) {
+                vulnerability.set_category(category);
+            }
+            if let Some(rule_id) = vuln.find("rule_id").and_then(|r| r.as_str()) {
+                vulnerability.set_rule_id(rule_id);
+            }
+            if let Some(rule_index) = vuln.find("rule_index").and_then(|r| r.as_u64()) {
+                vulnerability.set_rule_index(rule_index as usize);
+            }
+            if let Some(secret) = vuln.find("secret").and_then(|s| s.as_bool()) {
+                vulnerability.set_secret(secret);
+            }
+            if let Some(hash) = vuln.find("secret_hash").and_then(|h| h.as_str()) {
+                vulnerability.set_secret_hash(hash);
+            }
+            if let Some(message) = vuln.find("message").and_then(|m| m.as_str()) {
+                if message != description {
+                    vulnerability.set_message(message);
+                }
+            }
+            if let (Some(start), Some(end)) = (vuln.find("start_col").and_then(|c| c.as_u64()),
+                                               vuln.find("end_col").and_then(|c| c.as_u64())) {
+                vulnerability.set_columns(start as usize, end as usize);
+            }
+            if let (Some(start), Some(end)) = (vuln.find("start_offset").and_then(|o| o.as_u64()),
+                                               vuln.find("end_offset").and_then(|o| o.as_u64())) {
+                vulnerability.set_offsets(start as usize, end as usize);
+            }
+            vulnerabilities.push(vulnerability);
+        }
+
+        let whitelisted = match json.find("whitelisted_rules").and_then(|w| w.as_array()) {
+            Some(w) => w,
+            None => return None,
+        };
+
+        let mut whitelisted_rules = Vec::with_capacity(whitelisted.len());
+        for rule in whitelisted {
+            match rule.as_str() {
+                Some(r) => whitelisted_rules.push(String::from(r)),
+                None => return None,
+            }
+        }
+
+        match (json.find("size").and_then(|s| s.as_u64()),
+               json.find("modified").and_then(|m| m.as_u64()),
+               json.find("hash").and_then(|h| h.as_str()),
+               json.find("lines").and_then(|l| l.as_u64())) {
+            (Some(size), Some(modified), Some(hash), Some(lines)) => {
+                Some(CachedFile {
+                    size: size,
+                    modified: modified,
+                    hash: String::from(hash),
+                    findings: FileFindings::new(vulnerabilities,
+                                                whitelisted_rules,
+                                                lines as usize),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Gets the SHA-256 hash of the given contents as an hexadecimal string.
+pub fn get_hash<B: AsRef<[u8]>>(contents: B) -> String {
+    let mut sha256 = Sha256::new();
+    sha256.input(contents.as_ref());
+    sha256.result_str()
+}
+
+/// Gets a hash of everything that affects the findings apart from the analyzed files: the cache
+/// version, the rules, the analyzed folder and the loaded manifest of the application, with its
+/// SDK versions, or its absence.
+fn get_context_hash(config: &Config, rules_hash: &str, manifest: Option<&Manifest>) -> String {
+    let mut contents = format!("{}\0{}\0{}\0",
+                               CACHE_VERSION,
+                               rules_hash,
+                               config.get_analysis_folder());
+    match manifest {
+        Some(manifest) => {
+            contents.push_str(&format!("{}\0{:?}\0",
+                                       manifest.get_min_sdk(),
+                                       manifest.get_target_sdk()));
+            contents.push_str(manifest.get_code());
+        }
+        None => contents.push_str("no manifest"),
+    }
+
+    get_hash(contents)
+}
+
+/// Gets the modification time of the file in nanoseconds since the UNIX epoch.
+fn get_modified(metadata: &Metadata) -> u64 {
+    match metadata.modified().ok().and_then(|m| m.duration_since(UNIX_EPOCH).ok()) {
+        Some(d) => d.as_secs() * 1_000_000_000 + d.subsec_nanos() as u64,
+        None => 0,
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use regex::{Regex, quote};
+
+use {Config, Result, print_warning};
+
+/// Name of the file with the paths to exclude from the analysis, in the root of the source.
+pub const IGNORE_FILE: &'static str = ".superignore";
+
+/// Paths excluded from the code analysis, with the syntax of the `.gitignore` files.
+///
+/// Blank lines and lines starting with `#` are ignored, and patterns starting with `!` include
+/// again the paths excluded by previous patterns. Patterns ending with `/` only match directories.
+/// Patterns with a `/` in any other position are relative to the root of the source, while the
+/// rest match files and directories at any depth. `*` and `?` do not match `/`, but `**` does.
+#[derive(Debug, Default)]
+pub struct IgnoreFile {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug)]
+struct Pattern {
+    regex: Regex,
+    negated: bool,
+    only_dirs: bool,
+}
+
+impl IgnoreFile {
+    /// Loads the ignore file in the root of the source, if there is any.
+    pub fn load(config: &Config) -> Result<IgnoreFile> {
+        let path = Path::new(&config.get_analysis_folder()).join(IGNORE_FILE);
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+
+        let mut f = try!(File::open(&path));
+        let mut contents = String::new();
+        try!(f.read_to_string(&mut contents));
+        Ok(IgnoreFile::parse(&contents, config))
+    }
+
+    /// Parses the patterns of an ignore file. Invalid patterns are skipped with a warning.
+    pub fn parse(contents: &str, config: &Config) -> IgnoreFile {
+        let mut ignore: IgnoreFile = Default::default();
+        for line in contents.lines() {
+            let line = line.trim_right();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = if line.starts_with('!') {
+                (true, &line[1..])
+            } else {
+                (false, line.trim_left_matches('\\'))
+            };
+            let (only_dirs, line) = if line.ends_with('/') {
+                (true, line.trim_right_matches('/'))
+            } else {
+                (false, line)
+            };
+            let anchored = line.contains('/');
+            let line = line.trim_left_matches('/');
+
+            let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+            regex.push_str(&get_pattern_regex(line));
+            regex.push_str("$");
+            match Regex::new(&regex) {
+                Ok(r) => {
+                    ignore.patterns.push(Pattern {
+                        regex: r,
+                        negated: negated,
+                        only_dirs: only_dirs,
+                    })
+                }
+                Err(e) => {
+                    print_warning(format!("The pattern {} of the {} file is not valid, it will \
+                                           be ignored: {}",
+                                          line,
+                                          IGNORE_FILE,
+                                          e),
+                                  config.is_verbose())
+                }
+            }
+        }
+        ignore
+    }
+
+    /// Checks if the given path, relative to the root of the source, is excluded.
+    ///
+    /// As in `.gitignore` files, the last pattern matching the path decides.
+    pub fn is_ignored<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
+        let path = path.as_ref().to_string_lossy().replace('\\', "/");
+        let mut ignored = false;
+        for pattern in &self.patterns {
+            if (!pattern.only_dirs || is_dir) && pattern.regex.is_match(&path) {
+                ignored = !pattern.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Translates a glob pattern to a regular expression.
+fn get_pattern_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if i + 1 < chars.len() && chars[i + 1] == '*' => {
+                if i + 2 < chars.len() && chars[i + 2] == '/' {
+                    regex.push_str("(?:.*/)?");
+                    i += 1;
+                } else {
+                    regex.push_str(".*");
+                }
+                i += 1;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                match chars[i..].iter().position(|&c| c == ']') {
+                    Some(end) if end > 1 => {
+                        let class: String = chars[i + 1..i + end].iter().cloned().collect();
+                        regex.push('[');
+                        if class.starts_with('!') {
+                            regex.push('^');
+                            regex.push_str(&class[1..].replace('\\', "\\\\"));
+                        } else {
+                            regex.push_str(&class.replace('\\', "\\\\"));
+                        }
+                        regex.push(']');
+                        i += end;
+                    }
+                    _ => regex.push_str(&quote("[")),
+                }
+            }
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                regex.push_str(&quote(&chars[i].to_string()));
+            }
+            c => regex.push_str(&quote(&c.to_string())),
+        }
+        i += 1;
+    }
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use Config;
+    use super::IgnoreFile;
+
+    #[test]
+    fn it_ignore_patterns() {
+        let config: Config = Default::default();
+        let ignore = IgnoreFile::parse("# Generated code\n\
+                                        build/\n\
+                                        /com/example/*.xml\n\
+                                        **/test/**\n\
+                                        Debug?.java\n\
+                                        *.java\n\
+                                        !Main.java\n",
+                                       &config);
+
+        assert!(ignore.is_ignored("build", true));
+        assert!(ignore.is_ignored("app/build", true));
+        assert!(!ignore.is_ignored("build", false));
+        assert!(ignore.is_ignored("com/example/layout.xml", false));
+        assert!(!ignore.is_ignored("org/com/example/layout.xml", false));
+        assert!(!ignore.is_ignored("com/example/res/layout.xml", false));
+        assert!(ignore.is_ignored("com/test/Utils.kt", false));
+        assert!(ignore.is_ignored("com/example/Debug1.java", false));
+        assert!(ignore.is_ignored("com/example/Main2.java", false));
+        assert!(!ignore.is_ignored("com/example/Main.java", false));
+        assert!(!ignore.is_ignored("com/example/Main.kt", false));
+    }
+}
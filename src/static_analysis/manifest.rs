@@ -1,14 +1,20 @@
+use std::fs;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::fmt;
+use std::fmt::Display;
 use std::str::FromStr;
+use std::slice::Iter;
+use std::collections::BTreeMap;
 
 use yaml_rust::yaml::{Yaml, YamlLoader};
 use xml::reader::{EventReader, XmlEvent};
+use xml::common::Position;
 use colored::Colorize;
 
-use {Error, Config, Result, Criticity, print_error, print_warning, print_vulnerability, get_code,
-     get_string, PARSER_CONFIG};
+use {Error, Config, Result, Criticity, Confidence, print_error, print_warning, print_vulnerability,
+     get_code, get_string, PARSER_CONFIG};
 use results::{Results, Vulnerability};
 
 pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manifest> {
@@ -18,9 +24,7 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
     }
 
     let manifest =
-        match Manifest::load(format!("{}/{}/", config.get_dist_folder(), config.get_app_id()),
-                             config,
-                             results) {
+        match Manifest::load(config.get_analysis_folder(), config, results) {
             Ok(m) => {
                 if config.is_verbose() {
                     println!("{}", "The manifest was loaded successfully!".green());
@@ -72,7 +76,7 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
                            This allows any malicious person to inject arbitrary code in the \
                            application. This option should only be used while in development.";
 
-        let line = get_line(manifest.get_code(), "android:debuggable=\"true\"").ok();
+        let line = manifest.get_attribute_line("debuggable");
         let code = match line {
             Some(l) => Some(get_code(manifest.get_code(), l, l)),
             None => None,
@@ -98,7 +102,7 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
                              as such, but could be in devices with small heap. Check if the \
                              large heap is actually needed.";
 
-        let line = get_line(manifest.get_code(), "android:largeHeap=\"true\"").ok();
+        let line = manifest.get_attribute_line("largeHeap");
         let code = match line {
             Some(l) => Some(get_code(manifest.get_code(), l, l)),
             None => None,
@@ -124,7 +128,7 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
                            people with physical access could use adb to get private data of your \
                            app into their PC.";
 
-        let line = get_line(manifest.get_code(), "android:allowBackup=\"true\"").ok();
+        let line = manifest.get_attribute_line("allowBackup");
         let code = match line {
             Some(l) => Some(get_code(manifest.get_code(), l, l)),
             None => None,
@@ -144,9 +148,96 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
         }
     }
 
+    let providers = manifest.get_providers().filter(|p| {
+        p.is_exported(manifest.get_target_sdk()) && p.grants_uri_permissions() &&
+        !p.has_path_restrictions()
+    });
+    for provider in providers {
+        // A permission at least limits which applications can access the data directly
+        let criticity = if provider.is_protected() {
+            Criticity::Medium
+        } else {
+            Criticity::High
+        };
+        let description = format!("The {} content provider, with the {} authority, is exported and \
+                                   can grant temporary permissions to any of its data, since \
+                                   there are no grant-uri-permission or path-permission \
+                                   elements limiting its paths. Any component receiving one of \
+                                   its URIs could read or modify data that was not meant to be \
+                                   shared. Limit the paths that can be granted, or do not \
+                                   export the provider.",
+                                  provider.get_name(),
+                                  provider.get_authorities());
+
+        let line = provider.get_line();
+        let code = match line {
+            Some(l) => Some(get_code(manifest.get_code(), l, l)),
+            None => None,
+        };
+
+        let vuln = Vulnerability::new(criticity,
+                                      "Provider grants URI permissions",
+                                      description.as_str(),
+                                      Some("AndroidManifest.xml"),
+                                      line,
+                                      line,
+                                      code);
+        results.add_vulnerability(vuln);
+
+        if config.is_verbose() {
+            print_vulnerability(description, criticity);
+        }
+    }
+
+    for component in manifest.get_components().filter(|c| c.is_exported()) {
+        for filter in component.get_intent_filters() {
+            let data = match filter.get_broad_data() {
+                Some(d) => d,
+                None => continue,
+            };
+            let actions = filter.get_actions().cloned().collect::<Vec<_>>();
+            let actions = if actions.is_empty() {
+                String::from("without actions")
+            } else {
+                format!("for the {} actions", actions.join(", "))
+            };
+            let criticity = Criticity::Medium;
+            let description = format!("The {} {} is exported and its intent filter {} accepts \
+                                       {}. Any application can send it intents with arbitrary \
+                                       data, and it can intercept intents meant for other \
+                                       applications, such as links or shared files. Narrow the \
+                                       data of the filter with specific schemes, hosts and \
+                                       paths, or do not export the {}.",
+                                      component.get_kind(),
+                                      component.get_name(),
+                                      actions,
+                                      data,
+                                      component.get_kind());
+
+            let line = filter.get_line();
+            let code = match line {
+                Some(l) => Some(get_code(manifest.get_code(), l, l)),
+                None => None,
+            };
+
+            let vuln = Vulnerability::new(criticity,
+                                          "Overly broad intent filter",
+                                          description.as_str(),
+                                          Some("AndroidManifest.xml"),
+                                          line,
+                                          line,
+                                          code);
+            results.add_vulnerability(vuln);
+
+            if config.is_verbose() {
+                print_vulnerability(description, criticity);
+            }
+        }
+    }
+
     for permission in config.get_permissions() {
         if manifest.get_permission_checklist().needs_permission(permission.get_permission()) {
-            let line = get_line(manifest.get_code(), permission.get_permission().as_str()).ok();
+            let line = manifest.get_permission_line(permission.get_permission());
             let code = match line {
                 Some(l) => Some(get_code(manifest.get_code(), l, l)),
                 None => None,
@@ -178,6 +269,299 @@ pub fn manifest_analysis(config: &Config, results: &mut Results) -> Option<Manif
     Some(manifest)
 }
 
+/// Code that protects an activity against overlay attacks.
+const TAPJACKING_PROTECTIONS: [&'static str; 3] = ["setFilterTouchesWhenObscured(true)",
+                                                   "onFilterTouchEventForSecurity",
+                                                   "FLAG_WINDOW_IS_OBSCURED"];
+
+/// Checks if the exported activities of the application are protected against tapjacking.
+///
+/// Most applications do not need this protection, so the check only runs if the `tapjacking`
+/// category has been explicitly enabled.
+pub fn tapjacking_analysis(manifest: &Manifest, config: &Config, results: &mut Results) {
+    if !config.get_enabled_categories().any(|c| c == "tapjacking") {
+        return;
+    }
+
+    for activity in get_unprotected_activities(manifest, config) {
+        let criticity = Criticity::Medium;
+        let description = format!("The activity {} is exported and does not filter touches when \
+                                  it is obscured by another window. A malicious application \
+                                  could draw an overlay on top of it to trick the user into \
+                                  tapping on it (tapjacking). Use \
+                                  android:filterTouchesWhenObscured=\"true\" in the sensitive \
+                                  views of the activity.",
+                                  activity.get_name());
+
+        let line = activity.get_line();
+        let code = match line {
+            Some(l) => Some(get_code(manifest.get_code(), l, l)),
+            None => None,
+        };
+
+        let mut vuln = Vulnerability::new(criticity,
+                                          "Tapjacking",
+                                          description.as_str(),
+                                          Some("AndroidManifest.xml"),
+                                          line,
+                                          line,
+                                          code);
+        vuln.set_category("tapjacking");
+        results.add_vulnerability(vuln);
+
+        if config.is_verbose() {
+            print_vulnerability(description, criticity);
+        }
+    }
+}
+
+/// Gets the exported activities that are not protected against tapjacking.
+///
+/// An activity is protected if its source code filters obscured touches, or if any layout of the
+/// application sets `android:filterTouchesWhenObscured`, since layouts cannot be easily linked
+/// to the activities using them.
+fn get_unprotected_activities<'m>(manifest: &'m Manifest, config: &Config) -> Vec<&'m Activity> {
+    let folder = config.get_analysis_folder();
+    if has_protected_layout(format!("{}/res", folder)) {
+        return Vec::new();
+    }
+
+    manifest.get_activities()
+        .filter(|a| a.is_exported())
+        .filter(|a| {
+            let name = a.get_class_name(manifest.get_package());
+            let path = format!("{}/classes/{}.java", folder, name.replace('.', "/"));
+            let mut code = String::new();
+            match File::open(path).and_then(|mut f| f.read_to_string(&mut code)) {
+                Ok(_) => !TAPJACKING_PROTECTIONS.iter().any(|p| code.contains(p)),
+                Err(_) => true,
+            }
+        })
+        .collect()
+}
+
+/// Checks if any layout in the given resources folder filters obscured touches.
+fn has_protected_layout<P: AsRef<Path>>(res_folder: P) -> bool {
+    let dir_iter = match fs::read_dir(res_folder) {
+        Ok(d) => d,
+        Err(_) => return false,
+    };
+
+    for dir in dir_iter.filter_map(|d| d.ok()) {
+        if !dir.file_name().to_string_lossy().starts_with("layout") {
+            continue;
+        }
+        if let Ok(files) = fs::read_dir(dir.path()) {
+            for file in files.filter_map(|f| f.ok()) {
+                let mut code = String::new();
+                if File::open(file.path()).and_then(|mut f| f.read_to_string(&mut code)).is_ok() &&
+                   code.contains("filterTouchesWhenObscured=\"true\"") {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Names used in the code that handles sensitive input, in lowercase.
+const SENSITIVE_INPUTS: [&'static str; 9] = ["password",
+                                             "passcode",
+                                             "pincode",
+                                             "pin_code",
+                                             "cvv",
+                                             "cardnumber",
+                                             "card_number",
+                                             "creditcard",
+                                             "credit_card"];
+
+/// Code that prevents screenshots of a window. The decompiler might inline the value of
+/// `FLAG_SECURE`.
+const SCREENSHOT_PROTECTIONS: [&'static str; 3] = ["FLAG_SECURE",
+                                                   "addFlags(8192)",
+                                                   "setFlags(8192, 8192)"];
+
+/// Checks if the activities that handle sensitive input prevent screenshots of their windows.
+///
+/// Most applications do not need this protection, so the check only runs if the `screenshots`
+/// category has been explicitly enabled. The sensitive input is only detected by the names used
+/// in the code of the activities, so the findings have low confidence.
+pub fn screenshot_analysis(manifest: &Manifest, config: &Config, results: &mut Results) {
+    if !config.get_enabled_categories().any(|c| c == "screenshots") ||
+       config.get_min_confidence() > Confidence::Low {
+        return;
+    }
+
+    for activity in get_unsecured_activities(manifest, config) {
+        let criticity = Criticity::Low;
+        let description = format!("The activity {} seems to handle sensitive input, such as \
+                                  passwords or card numbers, but it does not set FLAG_SECURE in \
+                                  its window. Its contents can be captured in screenshots and \
+                                  screen recordings, and they are shown in the recent \
+                                  applications screen. Call getWindow().setFlags() with \
+                                  WindowManager.LayoutParams.FLAG_SECURE in its onCreate() \
+                                  method.",
+                                  activity.get_name());
+
+        let line = activity.get_line();
+        let code = match line {
+            Some(l) => Some(get_code(manifest.get_code(), l, l)),
+            None => None,
+        };
+
+        let mut vuln = Vulnerability::new(criticity,
+                                          "Missing FLAG_SECURE",
+                                          description.as_str(),
+                                          Some("AndroidManifest.xml"),
+                                          line,
+                                          line,
+                                          code);
+        vuln.set_confidence(Confidence::Low);
+        vuln.set_category("screenshots");
+        results.add_vulnerability(vuln);
+
+        if config.is_verbose() {
+            print_vulnerability(description, criticity);
+        }
+    }
+}
+
+/// Gets the activities whose source code handles sensitive input, but does not set
+/// `FLAG_SECURE`.
+///
+/// The activities without source code, such as the aliases, are skipped.
+fn get_unsecured_activities<'m>(manifest: &'m Manifest, config: &Config) -> Vec<&'m Activity> {
+    let folder = config.get_analysis_folder();
+    manifest.get_activities()
+        .filter(|a| {
+            let name = a.get_class_name(manifest.get_package());
+            let path = format!("{}/classes/{}.java", folder, name.replace('.', "/"));
+            let mut code = String::new();
+            match File::open(path).and_then(|mut f| f.read_to_string(&mut code)) {
+                Ok(_) => {
+                    let lowercase = code.to_lowercase();
+                    SENSITIVE_INPUTS.iter().any(|i| lowercase.contains(i)) &&
+                    !SCREENSHOT_PROTECTIONS.iter().any(|p| code.contains(p))
+                }
+                Err(_) => false,
+            }
+        })
+        .collect()
+}
+
+/// Code of the HTTP clients that connect to servers.
+const HTTP_CLIENTS: [&'static str; 5] = ["HttpURLConnection",
+                                         "HttpsURLConnection",
+                                         "OkHttpClient",
+                                         "org.apache.http.client",
+                                         "com.android.volley"];
+
+/// Code of the APIs that pin the certificates of the servers.
+const PINNING_APIS: [&'static str; 1] = ["CertificatePinner"];
+
+/// Checks if an application using the network pins the certificates of its servers.
+///
+/// Applications with the `INTERNET` permission and HTTP clients in their code should pin the
+/// certificates with a `<pin-set>` in their network security configuration or with the pinning
+/// APIs of their HTTP clients. The check only looks for these in the whole application, so its
+/// finding has low confidence.
+pub fn pinning_analysis(manifest: &Manifest, config: &Config, results: &mut Results) {
+    let internet = Permission::AndroidPermissionInternet;
+    if !manifest.get_permission_checklist().needs_permission(internet) ||
+       config.get_min_confidence() > Confidence::Low ||
+       config.get_disabled_categories().any(|c| c == "network") ||
+       (config.get_enabled_categories().len() > 0 &&
+        !config.get_enabled_categories().any(|c| c == "network")) {
+        return;
+    }
+
+    let folder = config.get_analysis_folder();
+    if has_pin_set(manifest, &folder) {
+        return;
+    }
+    let mut uses_client = false;
+    if scan_java_code(&folder, &mut uses_client) {
+        return;
+    }
+    if !uses_client {
+        return;
+    }
+
+    let criticity = Criticity::Low;
+    let description = "The application connects to servers with HTTP clients, but it does not \
+                       seem to pin their certificates. Without pinning, a certificate of any \
+                       trusted certificate authority, or one installed by the user, can be used \
+                       to intercept the connections. Add a <pin-set> to the network security \
+                       configuration, or use the pinning APIs of the HTTP client, such as the \
+                       CertificatePinner of OkHttp.";
+
+    let line = manifest.get_permission_line(internet);
+    let code = match line {
+        Some(l) => Some(get_code(manifest.get_code(), l, l)),
+        None => None,
+    };
+
+    let mut vuln = Vulnerability::new(criticity,
+                                      "Missing certificate pinning",
+                                      description,
+                                      Some("AndroidManifest.xml"),
+                                      line,
+                                      line,
+                                      code);
+    vuln.set_confidence(Confidence::Low);
+    vuln.set_category("network");
+    results.add_vulnerability(vuln);
+
+    if config.is_verbose() {
+        print_vulnerability(description, criticity);
+    }
+}
+
+/// Checks if the network security configuration of the application has a `<pin-set>`.
+fn has_pin_set(manifest: &Manifest, folder: &str) -> bool {
+    let resource = match manifest.get_network_security_config() {
+        Some(r) if r.starts_with("@xml/") => &r[5..],
+        _ => return false,
+    };
+    let mut code = String::new();
+    match File::open(format!("{}/res/xml/{}.xml", folder, resource))
+        .and_then(|mut f| f.read_to_string(&mut code)) {
+        Ok(_) => code.contains("<pin-set"),
+        Err(_) => false,
+    }
+}
+
+/// Looks for the HTTP clients and the pinning APIs in the Java files of the given folder,
+/// recursively, and returns if any pinning API is used.
+///
+/// `uses_client` is set if any HTTP client is used.
+fn scan_java_code<P: AsRef<Path>>(folder: P, uses_client: &mut bool) -> bool {
+    let dir_iter = match fs::read_dir(folder) {
+        Ok(d) => d,
+        Err(_) => return false,
+    };
+
+    for entry in dir_iter.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if scan_java_code(&path, uses_client) {
+                return true;
+            }
+        } else if path.extension().map_or(false, |e| e == "java") {
+            let mut code = String::new();
+            if File::open(&path).and_then(|mut f| f.read_to_string(&mut code)).is_ok() {
+                if PINNING_APIS.iter().any(|a| code.contains(a)) {
+                    return true;
+                }
+                if HTTP_CLIENTS.iter().any(|c| code.contains(c)) {
+                    *uses_client = true;
+                }
+            }
+        }
+    }
+    false
+}
+
 pub struct Manifest {
     code: String,
     package: String,
@@ -193,6 +577,12 @@ pub struct Manifest {
     install_location: InstallLocation,
     permissions: PermissionChecklist,
     debug: bool,
+    activities: Vec<Activity>,
+    components: Vec<Component>,
+    providers: Vec<Provider>,
+    network_security_config: Option<String>,
+    attribute_lines: BTreeMap<&'static str, usize>,
+    permission_lines: BTreeMap<Permission, usize>,
 }
 
 impl Manifest {
@@ -208,9 +598,16 @@ impl Manifest {
         manifest.set_code(code.as_str());
 
         let bytes = code.into_bytes();
-        let parser = EventReader::new_with_config(bytes.as_slice(), PARSER_CONFIG);
+        let mut parser = EventReader::new_with_config(bytes.as_slice(), PARSER_CONFIG);
+        let mut in_activity = false;
+        let mut in_component = false;
+        let mut in_intent_filter = false;
+        let mut in_provider = false;
 
-        for e in parser {
+        loop {
+            let e = parser.next();
+            // Line where the event starts, starting at 1.
+            let line = parser.position().row as usize + 1;
             match e {
                 Ok(XmlEvent::StartElement { name, attributes, .. }) => {
                     match name.local_name.as_str() {
@@ -278,6 +675,7 @@ impl Manifest {
                                         };
                                         if debug {
                                             manifest.set_debug();
+                                            manifest.set_attribute_line("debuggable", line);
                                         }
                                     }
                                     "allowBackup" => {
@@ -297,9 +695,13 @@ impl Manifest {
                                         };
                                         if allows_backup {
                                             manifest.set_allows_backup();
+                                            manifest.set_attribute_line("allowBackup", line);
                                         }
                                     }
                                     "description" => manifest.set_description(attr.value.as_str()),
+                                    "networkSecurityConfig" => {
+                                        manifest.network_security_config = Some(attr.value)
+                                    }
                                     "hasCode" => {
                                         let has_code = match attr.value.as_str().parse() {
                                             Ok(b) => b,
@@ -336,6 +738,7 @@ impl Manifest {
                                         };
                                         if large_heap {
                                             manifest.set_large_heap();
+                                            manifest.set_attribute_line("largeHeap", line);
                                         }
                                     }
                                     "label" => manifest.set_label(
@@ -359,29 +762,151 @@ impl Manifest {
                                 }
                             }
                         }
+                        "activity" | "activity-alias" | "service" | "receiver" => {
+                            // Services and receivers share the attributes of the activities
+                            let mut activity = Activity::default();
+                            for attr in attributes {
+                                match attr.name.local_name.as_str() {
+                                    "name" => {
+                                        activity.name = attr.value;
+                                        activity.line = Some(get_attribute_line(manifest.get_code(),
+                                                                                line,
+                                                                                "name"));
+                                    }
+                                    "exported" => {
+                                        match attr.value.as_str().parse() {
+                                            Ok(b) => activity.exported = Some(b),
+                                            Err(e) => {
+                                                print_warning(format!("An error occurred \
+                                                                       when parsing the \
+                                                                       exported attribute in \
+                                                                       the manifest: \
+                                                                       {}.\nThe process \
+                                                                       will continue, though.",
+                                                                      e),
+                                                              config.is_verbose());
+                                            }
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            manifest.components.push(Component {
+                                kind: name.local_name.clone(),
+                                name: activity.name.clone(),
+                                exported: activity.exported,
+                                intent_filters: Vec::new(),
+                            });
+                            in_component = true;
+                            if name.local_name.starts_with("activity") {
+                                manifest.activities.push(activity);
+                                in_activity = true;
+                            }
+                        }
+                        "provider" => {
+                            let mut provider = Provider::default();
+                            provider.line = Some(line);
+                            for attr in attributes {
+                                match attr.name.local_name.as_str() {
+                                    "name" => provider.name = attr.value,
+                                    "authorities" => {
+                                        provider.authorities = attr.value;
+                                        provider.line = Some(get_attribute_line(manifest.get_code(),
+                                                                                line,
+                                                                                "authorities"));
+                                    }
+                                    "exported" | "grantUriPermissions" => {
+                                        let value = match attr.value.as_str().parse() {
+                                            Ok(b) => b,
+                                            Err(e) => {
+                                                print_warning(format!("An error occurred \
+                                                                       when parsing the {} \
+                                                                       attribute in the \
+                                                                       manifest: {}.\nThe \
+                                                                       process will continue, \
+                                                                       though.",
+                                                                      attr.name.local_name,
+                                                                      e),
+                                                              config.is_verbose());
+                                                continue;
+                                            }
+                                        };
+                                        if attr.name.local_name == "exported" {
+                                            provider.exported = Some(value);
+                                        } else {
+                                            provider.grant_uri_permissions = value;
+                                        }
+                                    }
+                                    "permission" | "readPermission" | "writePermission" => {
+                                        provider.protected = true
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            manifest.providers.push(provider);
+                            in_provider = true;
+                        }
+                        "grant-uri-permission" | "path-permission" if in_provider => {
+                            if let Some(provider) = manifest.providers.last_mut() {
+                                provider.has_path_restrictions = true;
+                            }
+                        }
+                        "intent-filter" if in_component => {
+                            if in_activity {
+                                if let Some(activity) = manifest.activities.last_mut() {
+                                    activity.has_intent_filter = true;
+                                }
+                            }
+                            if let Some(component) = manifest.components.last_mut() {
+                                let mut filter = IntentFilter::default();
+                                filter.line = Some(line);
+                                component.intent_filters.push(filter);
+                            }
+                            in_intent_filter = true;
+                        }
+                        "action" | "data" if in_intent_filter => {
+                            let filter = match manifest.components
+                                .last_mut()
+                                .and_then(|c| c.intent_filters.last_mut()) {
+                                Some(f) => f,
+                                None => continue,
+                            };
+                            for attr in attributes {
+                                match (name.local_name.as_str(), attr.name.local_name.as_str()) {
+                                    ("action", "name") => filter.actions.push(attr.value),
+                                    ("data", "scheme") => filter.schemes.push(attr.value),
+                                    ("data", "host") => filter.hosts.push(attr.value),
+                                    ("data", "mimeType") => filter.mime_types.push(attr.value),
+                                    ("data", "pathPattern") => {
+                                        filter.path_patterns.push(attr.value)
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
                         "uses-permission" => {
+                            let mut permission = None;
+                            let mut max_sdk = None;
                             for attr in attributes {
                                 match attr.name.local_name.as_str() {
                                     "name" => {
-                                        let permission = match Permission::from_str(attr.value
+                                        permission = match Permission::from_str(attr.value
                                             .as_str()) {
-                                            Ok(p) => p,
+                                            Ok(p) => Some(p),
                                             Err(_) => {
-                                                let line = get_line(manifest.get_code(),
-                                                                    attr.value.as_str())
-                                                    .ok();
-                                                let code = match line {
-                                                    Some(l) => {
-                                                        Some(get_code(manifest.get_code(), l, l))
-                                                    }
-                                                    None => None,
-                                                };
+                                                let line = get_attribute_line(manifest.get_code(),
+                                                                              line,
+                                                                              "name");
+                                                let code = get_code(manifest.get_code(),
+                                                                    line,
+                                                                    line);
 
                                                 let vuln = Vulnerability::new(
                                                     config.get_unknown_permission_criticity(),
                                                     "Unknown permission",
                                                     config.get_unknown_permission_description(),
-                                                    Some("AndroidManifest.xml"), line, line, code);
+                                                    Some("AndroidManifest.xml"), Some(line),
+                                                    Some(line), Some(code));
                                                 results.add_vulnerability(vuln);
 
                                                 if config.is_verbose() {
@@ -392,16 +917,38 @@ impl Manifest {
                                                 break;
                                             }
                                         };
-                                        manifest.get_mut_permission_checklist()
-                                            .set_needs_permission(permission);
                                     }
+                                    "maxSdkVersion" => max_sdk = attr.value.parse().ok(),
                                     _ => {}
                                 }
                             }
+                            if let Some(permission) = permission {
+                                let permission_line =
+                                    get_attribute_line(manifest.get_code(), line, "name");
+                                manifest.permission_lines.insert(permission, permission_line);
+                                let checklist = manifest.get_mut_permission_checklist();
+                                checklist.set_needs_permission(permission);
+                                if let Some(max_sdk) = max_sdk {
+                                    checklist.set_max_sdk(permission, max_sdk);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(XmlEvent::EndElement { name }) => {
+                    match name.local_name.as_str() {
+                        "activity" | "activity-alias" => {
+                            in_activity = false;
+                            in_component = false;
                         }
+                        "service" | "receiver" => in_component = false,
+                        "intent-filter" => in_intent_filter = false,
+                        "provider" => in_provider = false,
                         _ => {}
                     }
                 }
+                Ok(XmlEvent::EndDocument) => break,
                 Ok(_) => {}
                 Err(e) => {
                     print_warning(format!("An error occurred when parsing the \
@@ -409,6 +956,7 @@ impl Manifest {
                                            continue, though.",
                                           e),
                                   config.is_verbose());
+                    break;
                 }
             }
         }
@@ -542,7 +1090,7 @@ impl Manifest {
         self.min_sdk
     }
 
-    fn set_min_sdk(&mut self, min_sdk: i32) {
+    pub fn set_min_sdk(&mut self, min_sdk: i32) {
         self.min_sdk = min_sdk;
     }
 
@@ -550,7 +1098,7 @@ impl Manifest {
         self.target_sdk
     }
 
-    fn set_target_sdk(&mut self, target_sdk: i32) {
+    pub fn set_target_sdk(&mut self, target_sdk: i32) {
         self.target_sdk = Some(target_sdk);
     }
 
@@ -594,10 +1142,51 @@ impl Manifest {
         self.debug = true;
     }
 
+    /// Gets the line of the given attribute of the application element, starting at 1.
+    ///
+    /// Only the lines of the `debuggable`, `allowBackup` and `largeHeap` attributes are stored,
+    /// and only if they are enabled.
+    pub fn get_attribute_line(&self, attribute: &str) -> Option<usize> {
+        self.attribute_lines.get(attribute).cloned()
+    }
+
+    /// Stores the line of an attribute of the element starting in the given line.
+    fn set_attribute_line(&mut self, attribute: &'static str, element_line: usize) {
+        let line = get_attribute_line(self.code.as_str(), element_line, attribute);
+        self.attribute_lines.insert(attribute, line);
+    }
+
+    /// Gets the line where the given permission is requested, starting at 1.
+    pub fn get_permission_line(&self, permission: Permission) -> Option<usize> {
+        self.permission_lines.get(&permission).cloned()
+    }
+
     pub fn get_permission_checklist(&self) -> &PermissionChecklist {
         &self.permissions
     }
 
+    pub fn get_activities(&self) -> Iter<Activity> {
+        self.activities.iter()
+    }
+
+    /// Gets the activities, activity aliases, services and receivers declared in the manifest.
+    pub fn get_components(&self) -> Iter<Component> {
+        self.components.iter()
+    }
+
+    pub fn get_providers(&self) -> Iter<Provider> {
+        self.providers.iter()
+    }
+
+    /// Gets the resource of the network security configuration of the application, such as
+    /// `@xml/network_security_config`, if it has one.
+    pub fn get_network_security_config(&self) -> Option<&str> {
+        match self.network_security_config.as_ref() {
+            Some(c) => Some(c.as_str()),
+            None => None,
+        }
+    }
+
     fn get_mut_permission_checklist(&mut self) -> &mut PermissionChecklist {
         &mut self.permissions
     }
@@ -620,10 +1209,187 @@ impl Default for Manifest {
             install_location: InstallLocation::InternalOnly,
             permissions: Default::default(),
             debug: false,
+            activities: Vec::new(),
+            components: Vec::new(),
+            providers: Vec::new(),
+            network_security_config: None,
+            attribute_lines: BTreeMap::new(),
+            permission_lines: BTreeMap::new(),
+        }
+    }
+}
+
+/// Activity declared in the manifest.
+#[derive(Default)]
+pub struct Activity {
+    name: String,
+    exported: Option<bool>,
+    has_intent_filter: bool,
+    line: Option<usize>,
+}
+
+impl Activity {
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets the line of the name of the activity in the manifest, starting at 1.
+    pub fn get_line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Gets the fully qualified class name of the activity.
+    pub fn get_class_name(&self, package: &str) -> String {
+        if self.name.starts_with('.') {
+            format!("{}{}", package, self.name)
+        } else if !self.name.contains('.') {
+            format!("{}.{}", package, self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Checks if other applications can start the activity.
+    ///
+    /// If the `exported` attribute is not set, the activity will be exported if it has an intent
+    /// filter.
+    pub fn is_exported(&self) -> bool {
+        self.exported.unwrap_or(self.has_intent_filter)
+    }
+}
+
+/// Activity, activity alias, service or receiver declared in the manifest, with its intent
+/// filters.
+pub struct Component {
+    kind: String,
+    name: String,
+    exported: Option<bool>,
+    intent_filters: Vec<IntentFilter>,
+}
+
+impl Component {
+    /// Gets the element declaring the component, such as `activity` or `receiver`.
+    pub fn get_kind(&self) -> &str {
+        self.kind.as_str()
+    }
+
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn get_intent_filters(&self) -> Iter<IntentFilter> {
+        self.intent_filters.iter()
+    }
+
+    /// Checks if other applications can use the component.
+    ///
+    /// If the `exported` attribute is not set, the component will be exported if it has an
+    /// intent filter.
+    pub fn is_exported(&self) -> bool {
+        self.exported.unwrap_or(!self.intent_filters.is_empty())
+    }
+}
+
+/// Schemes whose URIs can point to any web page, file or content if no host is given.
+const BROAD_SCHEMES: [&'static str; 4] = ["http", "https", "file", "content"];
+
+/// Intent filter of a component, with the attributes of its `action` and `data` elements.
+///
+/// All the `data` elements of a filter are combined, so a host of one of them applies to the
+/// schemes of the rest.
+#[derive(Default)]
+pub struct IntentFilter {
+    actions: Vec<String>,
+    schemes: Vec<String>,
+    hosts: Vec<String>,
+    mime_types: Vec<String>,
+    path_patterns: Vec<String>,
+    line: Option<usize>,
+}
+
+impl IntentFilter {
+    pub fn get_actions(&self) -> Iter<String> {
+        self.actions.iter()
+    }
+
+    /// Gets the line of the filter in the manifest, starting at 1.
+    pub fn get_line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Gets the data the filter accepts if it is overly broad: a wildcard scheme, host, MIME
+    /// type or path pattern, or a web, file or content scheme without any host.
+    pub fn get_broad_data(&self) -> Option<String> {
+        if self.schemes.iter().any(|s| s == "*") {
+            Some(String::from("any scheme"))
+        } else if self.hosts.iter().any(|h| h == "*") {
+            Some(String::from("any host"))
+        } else if let Some(t) = self.mime_types.iter().find(|t| *t == "*" || *t == "*/*") {
+            Some(format!("the {} MIME type", t))
+        } else if let Some(p) = self.path_patterns.iter().find(|p| *p == ".*" || *p == "/.*") {
+            Some(format!("the {} path pattern", p))
+        } else if self.hosts.is_empty() {
+            self.schemes
+                .iter()
+                .find(|s| BROAD_SCHEMES.contains(&s.as_str()))
+                .map(|s| format!("any {} URI", s))
+        } else {
+            None
         }
     }
 }
 
+/// Content provider declared in the manifest.
+#[derive(Default)]
+pub struct Provider {
+    name: String,
+    authorities: String,
+    exported: Option<bool>,
+    grant_uri_permissions: bool,
+    has_path_restrictions: bool,
+    protected: bool,
+    line: Option<usize>,
+}
+
+impl Provider {
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn get_authorities(&self) -> &str {
+        self.authorities.as_str()
+    }
+
+    /// Gets the line of the authorities of the provider in the manifest, starting at 1.
+    pub fn get_line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Checks if other applications can access the provider.
+    ///
+    /// If the `exported` attribute is not set, providers are exported in applications targeting
+    /// SDKs older than 17.
+    pub fn is_exported(&self, target_sdk: Option<i32>) -> bool {
+        self.exported.unwrap_or(target_sdk.map_or(true, |sdk| sdk < 17))
+    }
+
+    /// Checks if the provider allows granting temporary permissions to any of its data.
+    pub fn grants_uri_permissions(&self) -> bool {
+        self.grant_uri_permissions
+    }
+
+    /// Checks if the provider limits the paths of its permissions, with `grant-uri-permission`
+    /// or `path-permission` elements.
+    pub fn has_path_restrictions(&self) -> bool {
+        self.has_path_restrictions
+    }
+
+    /// Checks if the provider requires a permission to read or write its data.
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum InstallLocation {
     InternalOnly,
@@ -643,10 +1409,25 @@ impl FromStr for InstallLocation {
     }
 }
 
+/// Gets the line of the given attribute of the element starting in the given line.
+///
+/// The XML parser only gives the position of the elements, so the attribute is searched from the
+/// start of the element. If it cannot be found, the line of the element is returned.
+fn get_attribute_line(code: &str, element_line: usize, attribute: &str) -> usize {
+    let offset = code.split('\n').take(element_line - 1).map(|l| l.len() + 1).sum::<usize>();
+    if offset > code.len() {
+        return element_line;
+    }
+    match get_line(&code[offset..], &format!(":{}", attribute)) {
+        Ok(l) => element_line + l - 1,
+        Err(_) => element_line,
+    }
+}
+
 fn get_line(code: &str, haystack: &str) -> Result<usize> {
     for (i, line) in code.lines().enumerate() {
         if line.contains(haystack) {
-            return Ok(i);
+            return Ok(i + 1);
         }
     }
 
@@ -655,8 +1436,19 @@ fn get_line(code: &str, haystack: &str) -> Result<usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::{InstallLocation, Permission, PermissionChecklist, get_line};
+    use super::{InstallLocation, Permission, PermissionChecklist, ProtectionLevel, Manifest,
+                get_line, get_unprotected_activities, manifest_analysis, pinning_analysis,
+                get_unsecured_activities, screenshot_analysis};
     use std::str::FromStr;
+    use std::fs;
+    use std::fs::File;
+    use std::io::Write;
+
+    use serde_json;
+    use serde_json::value::Value;
+
+    use Config;
+    use results::{Results, ReportFormat};
 
     #[test]
     fn it_get_line() {
@@ -676,15 +1468,424 @@ mod tests {
         multiline code search. This should be
         enough, probably.";
 
-        assert_eq!(get_line(code1, "Razican").unwrap(), 0);
-        assert_eq!(get_line(code1, "multiline").unwrap(), 2);
-        assert_eq!(get_line(code2, "search").unwrap(), 3);
-        assert_eq!(get_line(code2, "probably").unwrap(), 4);
-        assert_eq!(get_line(code3, "create").unwrap(), 0);
-        assert_eq!(get_line(code3, "enough").unwrap(), 3);
+        assert_eq!(get_line(code1, "Razican").unwrap(), 1);
+        assert_eq!(get_line(code1, "multiline").unwrap(), 3);
+        assert_eq!(get_line(code2, "search").unwrap(), 4);
+        assert_eq!(get_line(code2, "probably").unwrap(), 5);
+        assert_eq!(get_line(code3, "create").unwrap(), 1);
+        assert_eq!(get_line(code3, "enough").unwrap(), 4);
         assert!(get_line(code3, "lalalala").is_err());
     }
 
+    #[test]
+    fn it_tapjacking() {
+        let folder = "test_tapjacking";
+        fs::create_dir_all(format!("{}/classes/com/example", folder)).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <application android:label=\"Example\">
+        <activity android:name=\".LoginActivity\">
+            <intent-filter>
+                <action android:name=\"android.intent.action.MAIN\" />
+            </intent-filter>
+        </activity>
+        <activity android:name=\"com.example.PaymentActivity\" android:exported=\"true\" />
+        <activity android:name=\"SettingsActivity\" />
+        <activity android:name=\".HiddenActivity\" android:exported=\"false\">
+            <intent-filter>
+                <action android:name=\"com.example.HIDDEN\" />
+            </intent-filter>
+        </activity>
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\nversionInfo:\n  versionCode: '1'\n  \
+                          versionName: '1.0'\n")
+                .unwrap();
+            let mut f = File::create(format!("{}/classes/com/example/PaymentActivity.java",
+                                             folder))
+                .unwrap();
+            f.write_all(b"view.setFilterTouchesWhenObscured(true);\n").unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        let mut results: Results = Default::default();
+        let manifest = Manifest::load(folder, &config, &mut results).unwrap();
+
+        let exported: Vec<&str> = manifest.get_activities()
+            .filter(|a| a.is_exported())
+            .map(|a| a.get_name())
+            .collect();
+        assert_eq!(exported, vec![".LoginActivity", "com.example.PaymentActivity"]);
+
+        let unprotected: Vec<String> = get_unprotected_activities(&manifest, &config)
+            .iter()
+            .map(|a| a.get_class_name(manifest.get_package()))
+            .collect();
+        fs::remove_dir_all(folder).unwrap();
+
+        assert_eq!(unprotected, vec!["com.example.LoginActivity"]);
+    }
+
+    #[test]
+    fn it_screenshot_analysis() {
+        let folder = "test_screenshot_analysis";
+        fs::create_dir_all(format!("{}/classes/com/example", folder)).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <application android:label=\"Example\">
+        <activity android:name=\".LoginActivity\" />
+        <activity android:name=\".PaymentActivity\" />
+        <activity android:name=\".SettingsActivity\" />
+        <activity android:name=\".MissingActivity\" />
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\nversionInfo:\n  versionCode: '1'\n  \
+                          versionName: '1.0'\n")
+                .unwrap();
+            // A login activity without FLAG_SECURE
+            let mut f = File::create(format!("{}/classes/com/example/LoginActivity.java",
+                                             folder))
+                .unwrap();
+            f.write_all(b"public class LoginActivity extends Activity {\n\
+                          \tprivate EditText passwordField;\n\
+                          }\n")
+                .unwrap();
+            let mut f = File::create(format!("{}/classes/com/example/PaymentActivity.java",
+                                             folder))
+                .unwrap();
+            f.write_all(b"public class PaymentActivity extends Activity {\n\
+                          \tprivate EditText cardNumber;\n\
+                          \tprotected void onCreate(Bundle b) {\n\
+                          \t\tgetWindow().setFlags(8192, 8192);\n\
+                          \t}\n\
+                          }\n")
+                .unwrap();
+            let mut f = File::create(format!("{}/classes/com/example/SettingsActivity.java",
+                                             folder))
+                .unwrap();
+            f.write_all(b"public class SettingsActivity extends Activity {}\n").unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        let mut results: Results = Default::default();
+        let manifest = Manifest::load(folder, &config, &mut results).unwrap();
+
+        let unsecured: Vec<String> = get_unsecured_activities(&manifest, &config)
+            .iter()
+            .map(|a| a.get_class_name(manifest.get_package()))
+            .collect();
+        assert_eq!(unsecured, vec!["com.example.LoginActivity"]);
+
+        // The check only runs if its category is enabled
+        let mut results: Results = Default::default();
+        screenshot_analysis(&manifest, &config, &mut results);
+        assert_eq!(results.get_total_findings(), 0);
+
+        config.enable_category("screenshots");
+        let mut results: Results = Default::default();
+        screenshot_analysis(&manifest, &config, &mut results);
+        fs::remove_dir_all(folder).unwrap();
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let low = json.find("low").and_then(|l| l.as_array()).unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].find("name").and_then(|n| n.as_str()),
+                   Some("Missing FLAG_SECURE"));
+        assert_eq!(low[0].find("category").and_then(|c| c.as_str()), Some("screenshots"));
+        assert_eq!(low[0].find("confidence").and_then(|c| c.as_str()), Some("low"));
+        assert_eq!(low[0].find("start_line").and_then(|l| l.as_u64()), Some(4));
+    }
+
+    #[test]
+    fn it_manifest_lines() {
+        let folder = "test_manifest_lines";
+        fs::create_dir_all(folder).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <uses-permission android:name=\"android.permission.INTERNET\" />
+    <uses-permission
+        android:name=\"com.example.permission.CUSTOM\" />
+    <application android:label=\"Example\"
+        android:allowBackup=\"true\"
+        android:debuggable = 'true'>
+        <activity android:exported=\"true\"
+            android:name=\".MainActivity\" />
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\nversionInfo:\n  versionCode: '1'\n  \
+                          versionName: '1.0'\n")
+                .unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        let mut results: Results = Default::default();
+        let manifest = Manifest::load(folder, &config, &mut results).unwrap();
+        fs::remove_dir_all(folder).unwrap();
+
+        assert!(manifest.is_debug());
+        assert_eq!(manifest.get_attribute_line("debuggable"), Some(8));
+        assert_eq!(manifest.get_attribute_line("allowBackup"), Some(7));
+        assert_eq!(manifest.get_attribute_line("largeHeap"), None);
+        assert_eq!(manifest.get_permission_line(Permission::AndroidPermissionInternet),
+                   Some(3));
+        assert_eq!(manifest.get_activities().next().and_then(|a| a.get_line()), Some(10));
+
+        // The unknown permission is reported while loading the manifest.
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let unknown = json.find("low").and_then(|l| l.as_array()).unwrap();
+        assert_eq!(unknown[0].find("start_line").and_then(|l| l.as_u64()), Some(5));
+    }
+
+    #[test]
+    fn it_provider_grant_uri_permissions() {
+        let folder = "test_provider_grant_uri_permissions";
+        fs::create_dir_all(folder).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <application android:label=\"Example\">
+        <provider android:name=\".FilesProvider\"
+            android:authorities=\"com.example.files\"
+            android:exported=\"true\"
+            android:grantUriPermissions=\"true\" />
+        <provider android:name=\".ImagesProvider\"
+            android:authorities=\"com.example.images\"
+            android:exported=\"true\"
+            android:grantUriPermissions=\"true\">
+            <grant-uri-permission android:pathPrefix=\"/shared/\" />
+        </provider>
+        <provider android:name=\".CacheProvider\"
+            android:authorities=\"com.example.cache\"
+            android:exported=\"false\"
+            android:grantUriPermissions=\"true\" />
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\n  targetSdkVersion: '25'\n\
+                          versionInfo:\n  versionCode: '1'\n  versionName: '1.0'\n")
+                .unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        config.set_quiet(true);
+        let mut results: Results = Default::default();
+        let manifest = manifest_analysis(&config, &mut results).unwrap();
+        fs::remove_dir_all(folder).unwrap();
+
+        let providers: Vec<_> = manifest.get_providers().collect();
+        assert_eq!(providers.len(), 3);
+        assert_eq!(providers[0].get_name(), ".FilesProvider");
+        assert!(!providers[0].has_path_restrictions());
+        assert!(providers[1].has_path_restrictions());
+        assert!(!providers[2].is_exported(manifest.get_target_sdk()));
+
+        // Only the provider without path restrictions is reported
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let high = json.find("high").and_then(|h| h.as_array()).unwrap();
+        assert_eq!(high.len(), 1);
+        assert_eq!(high[0].find("name").and_then(|n| n.as_str()),
+                   Some("Provider grants URI permissions"));
+        assert_eq!(high[0].find("start_line").and_then(|l| l.as_u64()), Some(5));
+        assert!(high[0]
+            .find("description")
+            .and_then(|d| d.as_str())
+            .unwrap()
+            .contains("The .FilesProvider content provider, with the com.example.files"));
+    }
+
+    #[test]
+    fn it_broad_intent_filter() {
+        let folder = "test_broad_intent_filter";
+        fs::create_dir_all(folder).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <application android:label=\"Example\">
+        <activity android:name=\".BrowserActivity\">
+            <intent-filter>
+                <action android:name=\"android.intent.action.VIEW\" />
+                <data android:scheme=\"https\" />
+            </intent-filter>
+        </activity>
+        <activity android:name=\".LinkActivity\">
+            <intent-filter>
+                <action android:name=\"android.intent.action.VIEW\" />
+                <data android:scheme=\"https\" android:host=\"www.example.com\"
+                    android:pathPrefix=\"/links/\" />
+            </intent-filter>
+        </activity>
+        <receiver android:name=\".FileReceiver\" android:exported=\"true\">
+            <intent-filter>
+                <action android:name=\"com.example.OPEN\" />
+                <data android:mimeType=\"*/*\" />
+            </intent-filter>
+        </receiver>
+        <service android:name=\".SyncService\" android:exported=\"false\">
+            <intent-filter>
+                <action android:name=\"com.example.SYNC\" />
+                <data android:scheme=\"content\" android:host=\"*\" />
+            </intent-filter>
+        </service>
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\n  targetSdkVersion: '25'\n\
+                          versionInfo:\n  versionCode: '1'\n  versionName: '1.0'\n")
+                .unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        config.set_quiet(true);
+        let mut results: Results = Default::default();
+        let manifest = manifest_analysis(&config, &mut results).unwrap();
+        fs::remove_dir_all(folder).unwrap();
+
+        let components: Vec<_> = manifest.get_components().collect();
+        assert_eq!(components.len(), 4);
+        assert_eq!(manifest.get_activities().count(), 2);
+        assert_eq!(components[2].get_kind(), "receiver");
+        assert!(!components[3].is_exported());
+        assert!(components[1].get_intent_filters().all(|f| f.get_broad_data().is_none()));
+        assert_eq!(components[3].get_intent_filters().next().unwrap().get_broad_data(),
+                   Some(String::from("any host")));
+
+        // The narrow filter and the filter of the private service are not reported
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let medium = json.find("medium").and_then(|m| m.as_array()).unwrap();
+        let broad: Vec<_> = medium.iter()
+            .filter(|v| {
+                v.find("name").and_then(|n| n.as_str()) == Some("Overly broad intent filter")
+            })
+            .collect();
+        assert_eq!(broad.len(), 2);
+        assert_eq!(broad[0].find("start_line").and_then(|l| l.as_u64()), Some(5));
+        let description = broad[0].find("description").and_then(|d| d.as_str()).unwrap();
+        assert!(description.contains(".BrowserActivity"));
+        assert!(description.contains("android.intent.action.VIEW"));
+        assert!(description.contains("any https URI"));
+        let description = broad[1].find("description").and_then(|d| d.as_str()).unwrap();
+        assert!(description.contains("receiver .FileReceiver"));
+        assert!(description.contains("*/* MIME type"));
+    }
+
+    #[test]
+    fn it_pinning_analysis() {
+        let folder = "test_pinning_analysis";
+        fs::create_dir_all(format!("{}/res/xml", folder)).unwrap();
+        fs::create_dir_all(format!("{}/smali/com/example", folder)).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+                  package=\"com.example\">
+    <uses-permission android:name=\"android.permission.INTERNET\" />
+    <application android:label=\"Example\"
+        android:networkSecurityConfig=\"@xml/network_security_config\">
+    </application>
+</manifest>")
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '24'\n  targetSdkVersion: '25'\n\
+                          versionInfo:\n  versionCode: '1'\n  versionName: '1.0'\n")
+                .unwrap();
+            let mut f = File::create(format!("{}/res/xml/network_security_config.xml", folder))
+                .unwrap();
+            f.write_all(b"<network-security-config>\n</network-security-config>\n").unwrap();
+            let mut f = File::create(format!("{}/smali/com/example/Api.java", folder)).unwrap();
+            f.write_all(b"OkHttpClient client = new OkHttpClient();\n").unwrap();
+        }
+
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        config.set_quiet(true);
+        let count = |config: &Config| {
+            let mut results: Results = Default::default();
+            let manifest = manifest_analysis(config, &mut results).unwrap();
+            let mut results: Results = Default::default();
+            pinning_analysis(&manifest, config, &mut results);
+            let mut json = Vec::new();
+            results.write_report(ReportFormat::Json, &mut json).unwrap();
+            let json: Value = serde_json::from_slice(&json).unwrap();
+            let low = json.find("low").and_then(|l| l.as_array()).unwrap();
+            if let Some(finding) = low.first() {
+                assert_eq!(finding.find("name").and_then(|n| n.as_str()),
+                           Some("Missing certificate pinning"));
+                assert_eq!(finding.find("start_line").and_then(|l| l.as_u64()), Some(3));
+            }
+            low.len()
+        };
+
+        // Without a pin-set, the missing pinning is reported
+        let without_pin_set = count(&config);
+
+        // With a pin-set in the network security configuration, it is not
+        {
+            let mut f = File::create(format!("{}/res/xml/network_security_config.xml", folder))
+                .unwrap();
+            f.write_all(b"<network-security-config>
+    <domain-config>
+        <domain>example.com</domain>
+        <pin-set>
+            <pin digest=\"SHA-256\">7HIpactkIAq2Y49orFOOQKurWxmmSFZhBCoQYcRhJ3Y=</pin>
+        </pin-set>
+    </domain-config>
+</network-security-config>\n")
+                .unwrap();
+        }
+        let with_pin_set = count(&config);
+
+        // Neither when the HTTP client pins the certificates
+        {
+            let mut f = File::create(format!("{}/res/xml/network_security_config.xml", folder))
+                .unwrap();
+            f.write_all(b"<network-security-config>\n</network-security-config>\n").unwrap();
+            let mut f = File::create(format!("{}/smali/com/example/Api.java", folder)).unwrap();
+            f.write_all(b"OkHttpClient client = new OkHttpClient.Builder()\n\
+                          .certificatePinner(new CertificatePinner.Builder().build())\n\
+                          .build();\n")
+                .unwrap();
+        }
+        let with_pinner = count(&config);
+        fs::remove_dir_all(folder).unwrap();
+
+        assert_eq!(without_pin_set, 1);
+        assert_eq!(with_pin_set, 0);
+        assert_eq!(with_pinner, 0);
+    }
+
     #[test]
     fn it_install_loc_from_str() {
         assert_eq!(InstallLocation::InternalOnly,
@@ -703,6 +1904,11 @@ mod tests {
 
         assert!(checklist.needs_permission(Permission::AndroidPermissionInternet));
         assert!(!checklist.needs_permission(Permission::AndroidPermissionWriteExternalStorage));
+        assert_eq!(checklist.get_max_sdk(Permission::AndroidPermissionInternet), None);
+
+        checklist.set_max_sdk(Permission::AndroidPermissionWriteExternalStorage, 18);
+        assert_eq!(checklist.get_max_sdk(Permission::AndroidPermissionWriteExternalStorage),
+                   Some(18));
     }
 
     #[test]
@@ -717,6 +1923,25 @@ mod tests {
                    "android.permission.WRITE_EXTERNAL_STORAGE");
         assert!(Permission::from_str("Razican").is_err());
     }
+
+    #[test]
+    fn it_permission_protection_level() {
+        let levels = &[("android.permission.INTERNET", ProtectionLevel::Normal),
+                       ("android.permission.CAMERA", ProtectionLevel::Dangerous),
+                       ("android.permission.READ_CONTACTS", ProtectionLevel::Dangerous),
+                       ("android.permission.ACCESS_FINE_LOCATION", ProtectionLevel::Dangerous),
+                       ("android.permission.INSTALL_PACKAGES", ProtectionLevel::Signature),
+                       ("android.permission.BIND_DEVICE_ADMIN", ProtectionLevel::Signature),
+                       ("com.android.vending.CHECK_LICENSE", ProtectionLevel::Normal)];
+        for &(name, level) in levels {
+            let permission = Permission::from_str(name).unwrap();
+            assert_eq!(permission.protection_level(), level);
+            assert_eq!(permission.is_dangerous(), level == ProtectionLevel::Dangerous);
+            assert_eq!(format!("{}", permission), name);
+            assert_eq!(Permission::from_str(&format!("{}", permission)).unwrap(), permission);
+        }
+        assert_eq!(format!("{}", ProtectionLevel::Signature), "signature");
+    }
 }
 
 #[derive(Debug)]
@@ -979,6 +2204,7 @@ pub struct PermissionChecklist {
     com_google_android_xmpp_permission_send_receive: bool,
     com_google_android_xmpp_permission_use_xmpp_endpoint: bool,
     com_google_android_xmpp_permission_xmpp_endpoint_broadcast: bool,
+    max_sdks: BTreeMap<Permission, i32>,
 }
 
 impl PermissionChecklist {
@@ -1607,6 +2833,15 @@ impl PermissionChecklist {
         }
     }
 
+    /// Gets the `android:maxSdkVersion` of the permission, if it is only requested up to an SDK.
+    pub fn get_max_sdk(&self, p: Permission) -> Option<i32> {
+        self.max_sdks.get(&p).cloned()
+    }
+
+    fn set_max_sdk(&mut self, p: Permission, max_sdk: i32) {
+        let _ = self.max_sdks.insert(p, max_sdk);
+    }
+
     fn set_needs_permission(&mut self, p: Permission) {
         match p {
             Permission::AndroidPermissionAccessAllExternalStorage => {
@@ -2573,6 +3808,7 @@ impl Default for PermissionChecklist {
             com_google_android_xmpp_permission_send_receive: false,
             com_google_android_xmpp_permission_use_xmpp_endpoint: false,
             com_google_android_xmpp_permission_xmpp_endpoint_broadcast: false,
+            max_sdks: BTreeMap::new(),
         }
     }
 }
@@ -3426,6 +4662,189 @@ impl Permission {
             }
         }
     }
+    /// Gets the protection level of the permission in the Android platform.
+    ///
+    /// Dangerous permissions give access to private data or resources of the user, and must be
+    /// granted by the user, while signature permissions are only granted to applications signed
+    /// with the same certificate as the platform or the application defining them. Permissions
+    /// that are not defined by the platform are considered normal.
+    pub fn protection_level(&self) -> ProtectionLevel {
+        match *self {
+            Permission::AndroidPermissionAccessCoarseLocation |
+            Permission::AndroidPermissionAccessFineLocation |
+            Permission::AndroidPermissionBodySensors |
+            Permission::AndroidPermissionCallPhone |
+            Permission::AndroidPermissionCamera |
+            Permission::AndroidPermissionGetAccounts |
+            Permission::AndroidPermissionProcessOutgoingCalls |
+            Permission::AndroidPermissionReadCalendar |
+            Permission::AndroidPermissionReadCallLog |
+            Permission::AndroidPermissionReadContacts |
+            Permission::AndroidPermissionReadExternalStorage |
+            Permission::AndroidPermissionReadPhoneState |
+            Permission::AndroidPermissionReadProfile |
+            Permission::AndroidPermissionReadSms |
+            Permission::AndroidPermissionReadSocialStream |
+            Permission::AndroidPermissionReceiveMms |
+            Permission::AndroidPermissionReceiveSms |
+            Permission::AndroidPermissionReceiveWapPush |
+            Permission::AndroidPermissionRecordAudio |
+            Permission::AndroidPermissionSendSms |
+            Permission::AndroidPermissionUseSip |
+            Permission::AndroidPermissionWriteCalendar |
+            Permission::AndroidPermissionWriteCallLog |
+            Permission::AndroidPermissionWriteContacts |
+            Permission::AndroidPermissionWriteExternalStorage |
+            Permission::AndroidPermissionWriteProfile |
+            Permission::AndroidPermissionWriteSocialStream |
+            Permission::ComAndroidVoicemailPermissionAddVoicemail => ProtectionLevel::Dangerous,
+            Permission::AndroidPermissionAccessAllExternalStorage |
+            Permission::AndroidPermissionAccessCheckinProperties |
+            Permission::AndroidPermissionAccessMockLocation |
+            Permission::AndroidPermissionAccessMtp |
+            Permission::AndroidPermissionAccountManager |
+            Permission::AndroidPermissionAsecAccess |
+            Permission::AndroidPermissionAsecCreate |
+            Permission::AndroidPermissionAsecDestroy |
+            Permission::AndroidPermissionAsecMountUnmount |
+            Permission::AndroidPermissionAsecRename |
+            Permission::AndroidPermissionBatteryStats |
+            Permission::AndroidPermissionBindAccessibilityService |
+            Permission::AndroidPermissionBindAppwidget |
+            Permission::AndroidPermissionBindCallService |
+            Permission::AndroidPermissionBindCarrierMessagingService |
+            Permission::AndroidPermissionBindCarrierServices |
+            Permission::AndroidPermissionBindChooserTargetService |
+            Permission::AndroidPermissionBindDeviceAdmin |
+            Permission::AndroidPermissionBindDirectorySearch |
+            Permission::AndroidPermissionBindDreamService |
+            Permission::AndroidPermissionBindIncallService |
+            Permission::AndroidPermissionBindInputMethod |
+            Permission::AndroidPermissionBindKeyguardAppwidget |
+            Permission::AndroidPermissionBindMidiDeviceService |
+            Permission::AndroidPermissionBindNfcService |
+            Permission::AndroidPermissionBindNotificationListenerService |
+            Permission::AndroidPermissionBindPrintService |
+            Permission::AndroidPermissionBindRemoteviews |
+            Permission::AndroidPermissionBindTelecomConnectionService |
+            Permission::AndroidPermissionBindTextService |
+            Permission::AndroidPermissionBindTvInput |
+            Permission::AndroidPermissionBindVoiceInteraction |
+            Permission::AndroidPermissionBindVpnService |
+            Permission::AndroidPermissionBindWallpaper |
+            Permission::AndroidPermissionBluetoothPrivileged |
+            Permission::AndroidPermissionBluetoothStack |
+            Permission::AndroidPermissionBroadcastPackageRemoved |
+            Permission::AndroidPermissionBroadcastSms |
+            Permission::AndroidPermissionBroadcastWapPush |
+            Permission::AndroidPermissionCallPrivileged |
+            Permission::AndroidPermissionCameraDisableTransmitLed |
+            Permission::AndroidPermissionCaptureAudioOutput |
+            Permission::AndroidPermissionCaptureSecureVideoOutput |
+            Permission::AndroidPermissionCaptureVideoOutput |
+            Permission::AndroidPermissionChangeComponentEnabledState |
+            Permission::AndroidPermissionChangeConfiguration |
+            Permission::AndroidPermissionClearAppCache |
+            Permission::AndroidPermissionConnectivityInternal |
+            Permission::AndroidPermissionControlLocationUpdates |
+            Permission::AndroidPermissionDeleteCacheFiles |
+            Permission::AndroidPermissionDeletePackages |
+            Permission::AndroidPermissionDiagnostic |
+            Permission::AndroidPermissionDump |
+            Permission::AndroidPermissionFactoryTest |
+            Permission::AndroidPermissionForceStopPackages |
+            Permission::AndroidPermissionGetAccountsPrivileged |
+            Permission::AndroidPermissionGetAppOpsStats |
+            Permission::AndroidPermissionGetDetailedTasks |
+            Permission::AndroidPermissionGlobalSearch |
+            Permission::AndroidPermissionGlobalSearchControl |
+            Permission::AndroidPermissionHardwareTest |
+            Permission::AndroidPermissionInstallLocationProvider |
+            Permission::AndroidPermissionInstallPackages |
+            Permission::AndroidPermissionInteractAcrossUsers |
+            Permission::AndroidPermissionInteractAcrossUsersFull |
+            Permission::AndroidPermissionLocationHardware |
+            Permission::AndroidPermissionLoopRadio |
+            Permission::AndroidPermissionManageActivityStacks |
+            Permission::AndroidPermissionManageDocuments |
+            Permission::AndroidPermissionManageUsb |
+            Permission::AndroidPermissionManageUsers |
+            Permission::AndroidPermissionMasterClear |
+            Permission::AndroidPermissionMediaContentControl |
+            Permission::AndroidPermissionModifyAppwidgetBindPermissions |
+            Permission::AndroidPermissionModifyPhoneState |
+            Permission::AndroidPermissionMountFormatFilesystems |
+            Permission::AndroidPermissionMountUnmountFilesystems |
+            Permission::AndroidPermissionNetAdmin |
+            Permission::AndroidPermissionNetTunneling |
+            Permission::AndroidPermissionPackageUsageStats |
+            Permission::AndroidPermissionReadDreamState |
+            Permission::AndroidPermissionReadFrameBuffer |
+            Permission::AndroidPermissionReadInputState |
+            Permission::AndroidPermissionReadLogs |
+            Permission::AndroidPermissionReadPrivilegedPhoneState |
+            Permission::AndroidPermissionReboot |
+            Permission::AndroidPermissionReceiveDataActivityChange |
+            Permission::AndroidPermissionReceiveEmergencyBroadcast |
+            Permission::AndroidPermissionRemoteAudioPlayback |
+            Permission::AndroidPermissionRequestInstallPackages |
+            Permission::AndroidPermissionRetrieveWindowContent |
+            Permission::AndroidPermissionSendRespondViaMessage |
+            Permission::AndroidPermissionSetAlwaysFinish |
+            Permission::AndroidPermissionSetAnimationScale |
+            Permission::AndroidPermissionSetDebugApp |
+            Permission::AndroidPermissionSetPreferredApplications |
+            Permission::AndroidPermissionSetProcessLimit |
+            Permission::AndroidPermissionSetScreenCompatibility |
+            Permission::AndroidPermissionSetTime |
+            Permission::AndroidPermissionSetTimeZone |
+            Permission::AndroidPermissionSetWallpaperComponent |
+            Permission::AndroidPermissionSignalPersistentProcesses |
+            Permission::AndroidPermissionStartAnyActivity |
+            Permission::AndroidPermissionStatusBar |
+            Permission::AndroidPermissionSystemAlertWindow |
+            Permission::AndroidPermissionUpdateDeviceStats |
+            Permission::AndroidPermissionWriteApnSettings |
+            Permission::AndroidPermissionWriteDreamState |
+            Permission::AndroidPermissionWriteGservices |
+            Permission::AndroidPermissionWriteMediaStorage |
+            Permission::AndroidPermissionWriteSecureSettings |
+            Permission::AndroidPermissionWriteSettings |
+            Permission::ComAndroidVoicemailPermissionReadVoicemail |
+            Permission::ComAndroidVoicemailPermissionWriteVoicemail |
+            Permission::ComGoogleAndroidC2dmPermissionSend => ProtectionLevel::Signature,
+            _ => ProtectionLevel::Normal,
+        }
+    }
+
+    /// Checks if the permission is dangerous, so that it must be granted by the user.
+    pub fn is_dangerous(&self) -> bool {
+        self.protection_level() == ProtectionLevel::Dangerous
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Protection level of an Android permission.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ProtectionLevel {
+    /// Permissions with a low risk, granted automatically at install time.
+    Normal,
+    /// Permissions giving access to private data or resources, granted by the user.
+    Dangerous,
+    /// Permissions only granted to applications signed with the same certificate as the one
+    /// defining them.
+    Signature,
+}
+
+impl Display for ProtectionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
 }
 
 impl FromStr for Permission {
@@ -1,33 +1,262 @@
 pub mod manifest;
 pub mod certificate;
 pub mod code;
+pub mod cache;
+pub mod ignore;
 
+use std::fs::File;
+use std::io::Read;
 use std::time::Instant;
+use std::sync::Arc;
+use std::thread;
+
+use rustc_serialize::hex::ToHex;
 
 use self::manifest::*;
 use self::certificate::*;
 use self::code::*;
+use self::cache::get_hash;
 use results::{Results, Benchmark};
-use Config;
+use {Config, Result, print_warning};
 
-pub fn static_analysis(config: &Config, results: &mut Results) {
+pub fn static_analysis(config: &Config,
+                       rules: Arc<Vec<Rule>>,
+                       results: &mut Results)
+                       -> Result<()> {
     if config.is_verbose() {
         println!("It's time to analyze the application. First, a static analysis will be \
                   performed, starting with the AndroidManifest.xml file and then going through \
                   the actual code. Let's start!");
     }
 
-    let manifest_start = Instant::now();
-    let manifest = manifest_analysis(config, results);
-    if config.is_bench() {
-        results.add_benchmark(Benchmark::new("Manifest analysis", manifest_start.elapsed()));
+    record_hashes(config, results);
+
+    // The certificates do not depend on the manifest, so they are analyzed at the same time, and
+    // their findings are added to the results once both analyses finish.
+    let certificate_handle = if config.is_certificate_analysis_enabled() {
+        let certificate_config = config.clone();
+        Some(thread::spawn(move || {
+            let certificate_start = Instant::now();
+            let mut certificate_results: Results = Default::default();
+            let certificate = certificate_analysis(&certificate_config,
+                                                   &mut certificate_results);
+            (certificate_results, certificate, certificate_start.elapsed())
+        }))
+    } else {
+        None
+    };
+
+    // Without the manifest, the code rules that require permissions never match.
+    let manifest = if config.is_manifest_analysis_enabled() {
+        let manifest_start = Instant::now();
+        let manifest = manifest_analysis(config, results);
+        if let Some(ref manifest) = manifest {
+            tapjacking_analysis(manifest, config, results);
+            screenshot_analysis(manifest, config, results);
+            pinning_analysis(manifest, config, results);
+        }
+        if config.is_bench() {
+            results.add_benchmark(Benchmark::new("Manifest analysis", manifest_start.elapsed()));
+        }
+        manifest
+    } else {
+        None
+    };
+
+    if let Some(certificate_handle) = certificate_handle {
+        match certificate_handle.join() {
+            Ok((certificate_results, _certificate, certificate_duration)) => {
+                results.merge_vulnerabilities(certificate_results);
+                if config.is_bench() {
+                    results.add_benchmark(Benchmark::new("Certificate analysis",
+                                                         certificate_duration));
+                }
+            }
+            Err(e) => {
+                print_warning(format!("An error occurred when joining the certificate \
+                                       analysis thread: Error: {:?}",
+                                      e),
+                              config.is_verbose())
+            }
+        }
+    }
+
+    if config.is_code_analysis_enabled() {
+        code_analysis(manifest, rules, config, results)
+    } else {
+        Ok(())
+    }
+}
+
+/// Records the SHA-256 hashes of the rule files in the results, so that the report can be tied to
+/// the exact rules that were used. The hash of the APK is part of its fingerprint.
+fn record_hashes(config: &Config, results: &mut Results) {
+    if config.is_verbose() && config.get_source_folder().is_none() {
+        println!("The SHA-256 hash of the APK is {}.",
+                 results.get_app_fingerprint().get_sha256().to_hex());
     }
 
-    let certificate_start = Instant::now();
-    let certificate = certificate_analysis(config, results);
-    if config.is_bench() {
-        results.add_benchmark(Benchmark::new("Certificate analysis", certificate_start.elapsed()));
+    for file in config.get_rules_files() {
+        // The rules read from the standard input cannot be read again.
+        if file == "-" {
+            continue;
+        }
+        let mut contents = Vec::new();
+        match File::open(file).and_then(|mut f| f.read_to_end(&mut contents)) {
+            Ok(_) => {
+                let hash = get_hash(&contents);
+                if config.is_verbose() {
+                    println!("The SHA-256 hash of the {} rule file is {}.", file, hash);
+                }
+                results.add_rules_file(file, &hash);
+            }
+            Err(e) => {
+                print_warning(format!("An error occurred when hashing the {} rule file: {}",
+                                      file,
+                                      e),
+                              config.is_verbose())
+            }
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::File;
+    use std::io::Write;
+    use std::process::Command;
+    use std::sync::Arc;
 
-    code_analysis(manifest, config, results);
+    use serde_json;
+    use serde_json::value::Value;
+    use Config;
+    use results::{Results, ReportFormat};
+    use super::{record_hashes, static_analysis};
+    use super::code::load_code_rules;
+
+    #[test]
+    fn it_record_hashes() {
+        let path = "test_record_hashes.json";
+        {
+            let mut f = File::create(path).unwrap();
+            f.write_all(b"[]").unwrap();
+        }
+        let mut config: Config = Default::default();
+        config.set_rules_json(path);
+        let mut results: Results = Default::default();
+        record_hashes(&config, &mut results);
+        fs::remove_file(path).unwrap();
+
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let rules_files = json.find("rules_files").and_then(|r| r.as_array()).unwrap();
+        assert_eq!(rules_files.len(), 1);
+        assert_eq!(rules_files[0].find("file").and_then(|f| f.as_str()), Some(path));
+        assert_eq!(rules_files[0].find("sha256").and_then(|h| h.as_str()),
+                   Some("4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945"));
+    }
+
+    /// Creates the decompiled folder of an application with a debuggable manifest, a certificate
+    /// issued by the Android Debug certificate authority and a source file that uses
+    /// `Math.random()`.
+    fn create_test_app(config: &Config) {
+        let folder = config.get_analysis_folder();
+        let meta_inf = format!("{}/original/META-INF", folder);
+        fs::create_dir_all(&meta_inf).unwrap();
+        fs::create_dir_all(format!("{}/com/example", folder)).unwrap();
+        {
+            let mut f = File::create(format!("{}/AndroidManifest.xml", folder)).unwrap();
+            f.write_all(format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\" package=\"{}\">
+    <application android:label=\"Example\" android:debuggable=\"true\" />
+</manifest>",
+                                config.get_app_id())
+                    .as_bytes())
+                .unwrap();
+            let mut f = File::create(format!("{}/apktool.yml", folder)).unwrap();
+            f.write_all(b"sdkInfo:\n  minSdkVersion: '16'\nversionInfo:\n  versionCode: '1'\n  \
+                          versionName: '1.0'\n")
+                .unwrap();
+            let mut f = File::create(format!("{}/com/example/Main.java", folder)).unwrap();
+            f.write_all(b"double r = Math.random();\n").unwrap();
+        }
+
+        let status = Command::new("openssl")
+            .args(&["req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "2", "-subj",
+                    "/CN=Android Debug", "-keyout"])
+            .arg(format!("{}/key.pem", meta_inf))
+            .arg("-out")
+            .arg(format!("{}/cert.pem", meta_inf))
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+        let status = Command::new("openssl")
+            .args(&["crl2pkcs7", "-nocrl", "-outform", "DER", "-certfile"])
+            .arg(format!("{}/cert.pem", meta_inf))
+            .arg("-out")
+            .arg(format!("{}/CERT.RSA", meta_inf))
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+    }
+
+    /// Gets the names of all the findings of the results.
+    fn get_finding_names(results: &Results) -> Vec<String> {
+        let mut json = Vec::new();
+        results.write_report(ReportFormat::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let mut names = Vec::new();
+        for criticity in &["warnings", "low", "medium", "high", "critical"] {
+            for vuln in json.find(criticity).and_then(|c| c.as_array()).unwrap() {
+                names.push(String::from(vuln.find("name").and_then(|n| n.as_str()).unwrap()));
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn it_concurrent_phases() {
+        let mut config: Config = Default::default();
+        config.set_app_id("com.example.concurrent");
+        config.set_cache_enabled(false);
+        config.set_quiet(true);
+        create_test_app(&config);
+
+        let mut results: Results = Default::default();
+        static_analysis(&config, Arc::new(Vec::new()), &mut results).unwrap();
+        fs::remove_dir_all(config.get_analysis_folder()).unwrap();
+
+        let names = get_finding_names(&results);
+        assert!(names.contains(&String::from("Manifest Debug")));
+        assert!(names.contains(&String::from("Android Debug Certificate")));
+    }
+
+    #[test]
+    fn it_disabled_phases() {
+        let mut config: Config = Default::default();
+        config.set_app_id("com.example.phases");
+        config.set_cache_enabled(false);
+        config.set_quiet(true);
+        create_test_app(&config);
+        let rules = Arc::new(load_code_rules(&config).unwrap());
+
+        for phases in 0..8 {
+            let (manifest, certificate, code) = (phases & 1 != 0, phases & 2 != 0, phases & 4 != 0);
+            config.set_manifest_analysis_enabled(manifest);
+            config.set_certificate_analysis_enabled(certificate);
+            config.set_code_analysis_enabled(code);
+            let mut results: Results = Default::default();
+            static_analysis(&config, rules.clone(), &mut results).unwrap();
+
+            let names = get_finding_names(&results);
+            assert_eq!(names.contains(&String::from("Manifest Debug")), manifest);
+            assert_eq!(names.contains(&String::from("Android Debug Certificate")), certificate);
+            assert_eq!(names.contains(&String::from("Math Random method")), code);
+        }
+        fs::remove_dir_all(config.get_analysis_folder()).unwrap();
+    }
 }
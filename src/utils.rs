@@ -1,4 +1,4 @@
-use std::{fs, io};
+use std::{fs, io, cmp};
 use std::path::Path;
 use std::io::{Read, Write};
 use std::time::Duration;
@@ -24,8 +24,12 @@ pub fn print_error<S: AsRef<str>>(error: S, verbose: bool) {
         .unwrap();
 
     if !verbose {
-        println!("If you need more information, try to run the program again with the {} flag.",
-                 "-v".bold());
+        io::stderr()
+            .write(&format!("If you need more information, try to run the program again with the \
+                             {} flag.\n",
+                            "-v".bold())
+                .into_bytes()[..])
+            .unwrap();
     } else {
         sleep(Duration::from_millis(200));
     }
@@ -40,39 +44,146 @@ pub fn print_warning<S: AsRef<str>>(warning: S, verbose: bool) {
         .unwrap();
 
     if !verbose {
-        println!("If you need more information, try to run the program again with the {} flag.",
-                 "-v".bold());
+        io::stderr()
+            .write(&format!("If you need more information, try to run the program again with the \
+                             {} flag.\n",
+                            "-v".bold())
+                .into_bytes()[..])
+            .unwrap();
     } else {
         sleep(Duration::from_millis(200));
     }
 }
 
+pub fn print_debug<S: AsRef<str>>(message: S) {
+    io::stderr()
+        .write(&format!("{} {}\n", "Debug:".bold().blue(), message.as_ref()).into_bytes()[..])
+        .unwrap();
+}
+
 pub fn print_vulnerability<S: AsRef<str>>(text: S, criticity: Criticity) {
+    if let Some(message) = format_vulnerability(text, criticity) {
+        // The message is written at once while holding the lock of the standard output, so that
+        // the findings of different analysis threads are never mixed in the same line.
+        {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            stdout.write_all(message.as_bytes()).unwrap();
+            stdout.flush().unwrap();
+        }
+        sleep(Duration::from_millis(200));
+    }
+}
+
+/// Formats the line printed when a vulnerability is found. Warnings are not printed.
+fn format_vulnerability<S: AsRef<str>>(text: S, criticity: Criticity) -> Option<String> {
     let text = text.as_ref();
     let start = format!("Possible {} criticity vulnerability found!:", criticity);
     let (start, message) = match criticity {
         Criticity::Low => (start.cyan(), text.cyan()),
         Criticity::Medium => (start.yellow(), text.yellow()),
         Criticity::High | Criticity::Critical => (start.red(), text.red()),
-        _ => return,
+        _ => return None,
     };
-    println!("{} {}", start, message);
-    sleep(Duration::from_millis(200));
+    Some(format!("{} {}\n", start, message))
 }
 
+/// Maximum number of characters of each line of a code snippet. Longer lines, such as the ones
+/// of minified files, are truncated so that they do not bloat the reports.
+pub const MAX_SNIPPET_LINE_LENGTH: usize = 300;
+
+/// Gets the code of the given lines, with up to 4 lines of context before and after them.
+///
+/// Line numbers start at 1. Lines longer than `MAX_SNIPPET_LINE_LENGTH` are truncated.
 pub fn get_code(code: &str, s_line: usize, e_line: usize) -> String {
+    get_snippet(code, (s_line, 1), (e_line, 1))
+}
+
+/// Gets the code between the given positions, as `(line, column)` pairs, with up to 4 lines of
+/// context before and after them.
+///
+/// Lines and columns start at 1, and the end column is the one of the character following the
+/// code. Lines longer than `MAX_SNIPPET_LINE_LENGTH` are truncated around the given code, so that
+/// it stays visible.
+pub fn get_snippet(code: &str, start: (usize, usize), end: (usize, usize)) -> String {
+    let (s_line, s_col) = start;
+    let (e_line, e_col) = end;
     let mut result = String::new();
     for (i, text) in code.lines().enumerate() {
-        if i >= (e_line + 5) {
+        let line = i + 1;
+        if line > e_line + 4 {
             break;
-        } else if (s_line >= 5 && i > s_line - 5) || (s_line < 5 && i < s_line + 5) {
-            result.push_str(text);
+        } else if line + 4 >= s_line {
+            let from = if line == s_line { s_col - 1 } else { 0 };
+            let to = if line == e_line { e_col - 1 } else { 0 };
+            result.push_str(&truncate_line(text, from, to));
             result.push_str("\n");
         }
     }
     result
 }
 
+/// Truncates the given line to `MAX_SNIPPET_LINE_LENGTH` characters, keeping the characters
+/// between `from` and `to` visible if possible, and marking the removed parts with ellipses.
+fn truncate_line(text: &str, from: usize, to: usize) -> String {
+    let length = text.chars().count();
+    if length <= MAX_SNIPPET_LINE_LENGTH {
+        return String::from(text);
+    }
+
+    let to = if to > from { to } else { from };
+    let start = if to - from >= MAX_SNIPPET_LINE_LENGTH {
+        from
+    } else {
+        let margin = (MAX_SNIPPET_LINE_LENGTH - (to - from)) / 2;
+        let start = if from > margin { from - margin } else { 0 };
+        if start + MAX_SNIPPET_LINE_LENGTH > length {
+            length - MAX_SNIPPET_LINE_LENGTH
+        } else {
+            start
+        }
+    };
+    let mut result = String::new();
+    if start > 0 {
+        result.push('…');
+    }
+    result.extend(text.chars().skip(start).take(MAX_SNIPPET_LINE_LENGTH));
+    if start + MAX_SNIPPET_LINE_LENGTH < length {
+        result.push('…');
+    }
+    result
+}
+
+/// Masks the given spans of the code with `*`, so that the secrets in them are not shown.
+///
+/// Lines start at 1, and columns are counted in characters starting at 1, with the end column
+/// being the one of the character following the span. The first and last 2 characters of each
+/// span are kept if it is longer than 8 characters, so that the secret can still be identified.
+/// Spans outside of the code are ignored.
+pub fn redact_secrets(code: &str, spans: &[((usize, usize), (usize, usize))]) -> String {
+    let mut lines: Vec<Vec<char>> = code.split('\n').map(|l| l.chars().collect()).collect();
+    for &((start_line, start_col), (end_line, end_col)) in spans {
+        let mut positions = Vec::new();
+        for line in cmp::max(start_line, 1)..end_line + 1 {
+            if line > lines.len() {
+                break;
+            }
+            let index = line - 1;
+            let start = if line == start_line { start_col.saturating_sub(1) } else { 0 };
+            let end = if line == end_line { end_col.saturating_sub(1) } else { lines[index].len() };
+            for col in start..cmp::min(end, lines[index].len()) {
+                positions.push((index, col));
+            }
+        }
+        let visible = if positions.len() > 8 { 2 } else { 0 };
+        for &(line, col) in positions.iter().skip(visible).take(positions.len() - 2 * visible) {
+            lines[line][col] = '*';
+        }
+    }
+    let redacted: Vec<String> = lines.into_iter().map(|l| l.into_iter().collect()).collect();
+    redacted.join("\n")
+}
+
 pub fn file_exists<P: AsRef<Path>>(path: P) -> bool {
     path.as_ref().exists()
 }
@@ -125,9 +236,14 @@ pub fn get_string(label: &str, config: &Config) -> Result<String> {
 
 #[cfg(test)]
 mod test {
-    use {get_code, file_exists};
+    use {get_code, get_snippet, file_exists, Criticity, MAX_SNIPPET_LINE_LENGTH};
+    use super::format_vulnerability;
     use std::fs;
     use std::fs::File;
+    use std::io::Write;
+    use std::iter::repeat;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
 
     #[test]
     fn it_get_code() {
@@ -145,7 +261,7 @@ mod test {
                     mattis, tortor neque adipiscing\nVestibulum ante ipsum primis in faucibus \
                     orci luctus et ultrices";
 
-        assert_eq!(get_code(code, 1, 1),
+        assert_eq!(get_code(code, 2, 2),
                    "Lorem ipsum dolor sit amet, consectetur adipiscing elit.\n\
                     Curabitur tortor. Pellentesque nibh. Aenean quam.\n\
                     Sed lacinia, urna non tincidunt mattis, tortor neque\n\
@@ -153,7 +269,7 @@ mod test {
                     Sed aliquet risus a tortor. Integer id quam. Morbi mi.\n\
                     Nullam mauris orci, aliquet et, iaculis et, viverra vitae, ligula.\n");
 
-        assert_eq!(get_code(code, 13, 13),
+        assert_eq!(get_code(code, 14, 14),
                    "Vestibulum tincidunt malesuada tellus. Ut ultrices ultrices enim.\n\
                     Aenean laoreet. Vestibulum nisi lectus, commodo ac, facilisis\n\
                     Integer nec odio. Praesent libero. Sed cursus ante dapibus diam.\n\
@@ -161,7 +277,7 @@ mod test {
                     Sed lacinia, urna non tincidunt mattis, tortor neque adipiscing\n\
                     Vestibulum ante ipsum primis in faucibus orci luctus et ultrices\n");
 
-        assert_eq!(get_code(code, 7, 7),
+        assert_eq!(get_code(code, 8, 8),
                    "Praesent blandit dolor. Sed non quam. In vel mi\n\
                     Sed aliquet risus a tortor. Integer id quam. Morbi mi.\n\
                     Nullam mauris orci, aliquet et, iaculis et, viverra vitae, ligula.\n\
@@ -172,7 +288,7 @@ mod test {
                     Aenean laoreet. Vestibulum nisi lectus, commodo ac, facilisis\n\
                     Integer nec odio. Praesent libero. Sed cursus ante dapibus diam.\n");
 
-        assert_eq!(get_code(code, 7, 9),
+        assert_eq!(get_code(code, 8, 10),
                    "Praesent blandit dolor. Sed non quam. In vel mi\n\
                     Sed aliquet risus a tortor. Integer id quam. Morbi mi.\n\
                     Nullam mauris orci, aliquet et, iaculis et, viverra vitae, ligula.\n\
@@ -186,6 +302,74 @@ mod test {
                     Sed lacinia, urna non tincidunt mattis, tortor neque adipiscing\n");
     }
 
+    #[test]
+    fn it_get_snippet_long_line() {
+        let minified = format!("{}var key = \"secret\";{}",
+                               repeat('a').take(100_000).collect::<String>(),
+                               repeat('b').take(100_000).collect::<String>());
+        let code = format!("int a = 1;\n{}\nint b = 2;", minified);
+        let start = 100_001;
+        let end = start + "var key = \"secret\";".len();
+
+        let snippet = get_snippet(&code, (2, start), (2, end));
+        let lines: Vec<&str> = snippet.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "int a = 1;");
+        assert_eq!(lines[2], "int b = 2;");
+        assert!(lines[1].starts_with("…a"));
+        assert!(lines[1].ends_with("b…"));
+        assert!(lines[1].contains("var key = \"secret\";"));
+        assert_eq!(lines[1].chars().count(), MAX_SNIPPET_LINE_LENGTH + 2);
+
+        // Without the position of the code, the start of the line is kept
+        let snippet = get_code(&code, 2, 2);
+        let line = snippet.lines().nth(1).unwrap();
+        assert!(line.starts_with("aaa"));
+        assert!(line.ends_with("a…"));
+
+        // Short lines are not truncated
+        assert_eq!(get_snippet("int a = 1;", (1, 5), (1, 6)), "int a = 1;\n");
+    }
+
+    #[test]
+    fn it_vulnerability_output() {
+        assert!(format_vulnerability("Test", Criticity::Warning).is_none());
+
+        // Messages written like in print_vulnerability() from many threads must not be mixed.
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let criticities = [Criticity::Low, Criticity::Medium, Criticity::High, Criticity::Critical];
+        let handles: Vec<_> = criticities.iter()
+            .map(|&criticity| {
+                let output = output.clone();
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        let message = format_vulnerability(format!("Vulnerability {}", i),
+                                                           criticity)
+                            .unwrap();
+                        output.lock().unwrap().write_all(message.as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let output = String::from_utf8(Arc::try_unwrap(output).unwrap().into_inner().unwrap())
+            .unwrap();
+        let mut expected = Vec::new();
+        for &criticity in &criticities {
+            for i in 0..200 {
+                expected.push(format_vulnerability(format!("Vulnerability {}", i), criticity)
+                    .unwrap());
+            }
+        }
+        let mut lines: Vec<_> = output.lines().map(|l| format!("{}\n", l)).collect();
+        expected.sort();
+        lines.sort();
+        assert_eq!(lines, expected);
+    }
+
     #[test]
     fn it_file_exists() {
         if file_exists("test.txt") {
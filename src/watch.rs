@@ -0,0 +1,264 @@
+use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use colored::Colorize;
+
+use static_analysis::code::{Rule, load_code_rules, code_analysis};
+use static_analysis::manifest::Manifest;
+use results::Results;
+use {Config, Result, print_error};
+
+/// Milliseconds between two checks of the watched files.
+const POLL_INTERVAL: u64 = 500;
+/// Milliseconds without changes needed before analyzing again, so that a burst of changes, such
+/// as the ones made when saving several files, only triggers one analysis.
+const DEBOUNCE: u64 = 300;
+
+/// Source of the changes that trigger a new analysis in watch mode.
+pub trait EventSource {
+    /// Blocks until something changes, and returns the changed paths. If it returns `None`, the
+    /// watch mode will finish.
+    fn wait_for_changes(&mut self) -> Option<Vec<PathBuf>>;
+}
+
+/// Watches files and folders by checking the modification time and size of their files.
+pub struct PollingWatcher {
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    debounce: Duration,
+    snapshot: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl PollingWatcher {
+    /// Creates a watcher for the given files and folders, with the default intervals.
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> PollingWatcher {
+        PollingWatcher::with_intervals(paths,
+                                       Duration::from_millis(POLL_INTERVAL),
+                                       Duration::from_millis(DEBOUNCE))
+    }
+
+    /// Creates a watcher that checks the files every `interval`, and reports the changes once
+    /// there have been no more changes for `debounce`.
+    pub fn with_intervals<P: AsRef<Path>>(paths: &[P],
+                                          interval: Duration,
+                                          debounce: Duration)
+                                          -> PollingWatcher {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+        let snapshot = get_snapshot(&paths);
+        PollingWatcher {
+            paths: paths,
+            interval: interval,
+            debounce: debounce,
+            snapshot: snapshot,
+        }
+    }
+}
+
+impl EventSource for PollingWatcher {
+    fn wait_for_changes(&mut self) -> Option<Vec<PathBuf>> {
+        loop {
+            sleep(self.interval);
+            let mut current = get_snapshot(&self.paths);
+            if current == self.snapshot {
+                continue;
+            }
+
+            // Wait until the files stop changing
+            loop {
+                sleep(self.debounce);
+                let next = get_snapshot(&self.paths);
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+
+            let mut changes = BTreeSet::new();
+            for (path, state) in &current {
+                if self.snapshot.get(path) != Some(state) {
+                    changes.insert(path.clone());
+                }
+            }
+            for path in self.snapshot.keys() {
+                if !current.contains_key(path) {
+                    changes.insert(path.clone());
+                }
+            }
+            self.snapshot = current;
+            if !changes.is_empty() {
+                return Some(changes.into_iter().collect());
+            }
+        }
+    }
+}
+
+/// Gets the modification time and size of all the files in the given paths.
+fn get_snapshot(paths: &[PathBuf]) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+    let mut snapshot = BTreeMap::new();
+    for path in paths {
+        add_to_snapshot(path, &mut snapshot);
+    }
+    snapshot
+}
+
+fn add_to_snapshot(path: &Path, snapshot: &mut BTreeMap<PathBuf, (Option<SystemTime>, u64)>) {
+    let metadata = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                add_to_snapshot(&entry.path(), snapshot);
+            }
+        }
+    } else {
+        snapshot.insert(path.to_path_buf(),
+                        (metadata.modified().ok(), metadata.len()));
+    }
+}
+
+/// Analyzes the code of the application each time the source folder or the rule files change.
+///
+/// Only the code analysis is performed, and a summary of the new and fixed findings is printed
+/// after each analysis. The rules are loaded again only if a rule file changed, and errors in the
+/// analysis do not stop the watch mode, so that they can be fixed while it runs. Returns the
+/// number of analyses performed once the event source finishes.
+pub fn watch<E: EventSource>(config: &Config, events: &mut E) -> usize {
+    let mut rules = None;
+    let mut previous = None;
+    let mut analyses = 0;
+    loop {
+        if rules.is_none() {
+            rules = load_code_rules(config).ok().map(Arc::new);
+        }
+        if let Some(ref rules) = rules {
+            match analyze_code(config, rules.clone()) {
+                Ok(findings) => {
+                    if !config.is_quiet() {
+                        print_summary(previous.as_ref(), &findings);
+                    }
+                    previous = Some(findings);
+                }
+                Err(e) => {
+                    print_error(format!("The code could not be analyzed: {}", e),
+                                config.is_verbose())
+                }
+            }
+            analyses += 1;
+        }
+
+        if !config.is_quiet() {
+            println!("Waiting for changes in {}…", config.get_analysis_folder());
+        }
+        let changes = match events.wait_for_changes() {
+            Some(c) => c,
+            None => return analyses,
+        };
+        if !config.is_quiet() {
+            println!("");
+            println!("{}",
+                     format!("{} changed files, analyzing again.", changes.len()).bold());
+        }
+        let rules_files = config.get_rules_files();
+        if changes.iter().any(|c| rules_files.iter().any(|f| Path::new(f) == c.as_path())) {
+            rules = None;
+        }
+    }
+}
+
+/// Analyzes the code of the application, and returns the fingerprints of the findings.
+fn analyze_code(config: &Config, rules: Arc<Vec<Rule>>) -> Result<BTreeSet<String>> {
+    // The manifest findings are not reported, it is only needed by the rules requiring permissions
+    let manifest = Manifest::load(config.get_analysis_folder(),
+                                  config,
+                                  &mut Default::default())
+        .ok();
+    let mut results: Results = Default::default();
+    try!(code_analysis(manifest, rules, config, &mut results));
+    Ok(results.get_fingerprints())
+}
+
+/// Prints the number of findings, and the ones that are new or fixed since the last analysis.
+fn print_summary(previous: Option<&BTreeSet<String>>, findings: &BTreeSet<String>) {
+    match previous {
+        Some(previous) => {
+            println!("{} findings: {} new, {} fixed.",
+                     findings.len(),
+                     findings.difference(previous).count(),
+                     previous.difference(findings).count())
+        }
+        None => println!("{} findings.", findings.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use Config;
+    use super::{EventSource, PollingWatcher, watch};
+
+    /// Event source that changes the analyzed file once, and then stops the watch mode.
+    struct FileChange {
+        path: String,
+        changed: bool,
+    }
+
+    impl EventSource for FileChange {
+        fn wait_for_changes(&mut self) -> Option<Vec<PathBuf>> {
+            if self.changed {
+                return None;
+            }
+            let mut f = File::create(&self.path).unwrap();
+            f.write_all(b"double r = Math.random();\n").unwrap();
+            self.changed = true;
+            Some(vec![PathBuf::from(&self.path)])
+        }
+    }
+
+    #[test]
+    fn it_watch() {
+        let folder = "test_watch";
+        fs::create_dir_all(folder).unwrap();
+        let path = format!("{}/Test.java", folder);
+        {
+            let mut f = File::create(&path).unwrap();
+            f.write_all(b"int a = 1;\n").unwrap();
+        }
+        let mut config: Config = Default::default();
+        config.set_source_folder(folder);
+        config.set_cache_enabled(false);
+        config.set_quiet(true);
+
+        let mut events = FileChange {
+            path: path.clone(),
+            changed: false,
+        };
+        assert_eq!(watch(&config, &mut events), 2);
+        assert!(events.changed);
+
+        // Several changes in a row are reported together
+        let mut watcher = PollingWatcher::with_intervals(&[folder],
+                                                         Duration::from_millis(10),
+                                                         Duration::from_millis(50));
+        {
+            let mut f = File::create(&path).unwrap();
+            f.write_all(b"int b = 2;\n").unwrap();
+            let mut f = File::create(format!("{}/Other.java", folder)).unwrap();
+            f.write_all(b"int c = 3;\n").unwrap();
+        }
+        let changes = watcher.wait_for_changes().unwrap();
+        fs::remove_dir_all(folder).unwrap();
+        assert_eq!(changes,
+                   vec![PathBuf::from(format!("{}/Other.java", folder)), PathBuf::from(path)]);
+    }
+}